@@ -0,0 +1,417 @@
+use crate::{Instruction, Op, Register};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Identifies encoded programs, followed by a format version byte.
+pub const MAGIC: &[u8; 4] = b"CPU\x01";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    InvalidInstruction(String),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UnknownLabel(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// 1-based line number in the source
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AsmErrorKind::InvalidInstruction(instr) => write!(f, "invalid instruction `{}`", instr),
+            AsmErrorKind::InvalidLabel(label) => write!(f, "invalid label `{}`", label),
+            AsmErrorKind::DuplicateLabel(label) => write!(f, "label `{}` is already defined", label),
+            AsmErrorKind::UnknownLabel(label) => write!(f, "unknown label `{}`", label),
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    BadMagic,
+    UnknownOpcode(u8),
+    InvalidRegister(u8),
+    Truncated,
+    Overflow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Byte offset of the instruction that couldn't be decoded
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: ", self.offset)?;
+        match &self.kind {
+            DecodeErrorKind::BadMagic => write!(f, "not an encoded program"),
+            DecodeErrorKind::UnknownOpcode(op) => write!(f, "unknown opcode {:#04x}", op),
+            DecodeErrorKind::InvalidRegister(reg) => write!(f, "invalid register {}", reg),
+            DecodeErrorKind::Truncated => write!(f, "unexpected end of program"),
+            DecodeErrorKind::Overflow => write!(f, "immediate out of range"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A source line with comments stripped and any leading label split off.
+struct SourceLine<'a> {
+    line: usize,
+    label: Option<&'a str>,
+    instr: Option<&'a str>,
+}
+
+fn split_lines(source: &str) -> impl Iterator<Item = SourceLine<'_>> {
+    source.lines().enumerate().map(|(i, text)| {
+        let text = text.split([';', '#']).next().unwrap_or("").trim();
+        let (label, instr) = match text.split_once(':') {
+            Some((label, rest)) => (Some(label.trim()), rest.trim()),
+            None => (None, text),
+        };
+
+        SourceLine {
+            line: i + 1,
+            label,
+            instr: Some(instr).filter(|instr| !instr.is_empty()),
+        }
+    })
+}
+
+/// Assembles source text into instructions.
+///
+/// On top of the plain format read by `Instruction::parse`, sources may
+/// contain comments starting with `;` or `#`, blank lines, and labels (a
+/// name followed by `:`, on its own line or before an instruction). Jumps
+/// may target a label instead of a relative offset.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AsmError> {
+    // First pass: find the instruction index of every label
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut count = 0;
+    for line in split_lines(source) {
+        if let Some(label) = line.label {
+            let error = |kind| AsmError { line: line.line, kind };
+            if !is_label(label) {
+                return Err(error(AsmErrorKind::InvalidLabel(label.to_string())));
+            }
+            if labels.insert(label, count).is_some() {
+                return Err(error(AsmErrorKind::DuplicateLabel(label.to_string())));
+            }
+        }
+        if line.instr.is_some() {
+            count += 1;
+        }
+    }
+
+    // Second pass: swap label targets for offsets, then parse as usual
+    let mut program: Vec<Instruction> = vec![];
+    for line in split_lines(source) {
+        let Some(text) = line.instr else { continue };
+        let error = |kind| AsmError { line: line.line, kind };
+
+        let mut parts: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+        // Only a jump with the right number of arguments has a target, so a
+        // missing offset isn't mistaken for a label
+        let arity = match parts[0].as_str() {
+            "jmp" => Some(2),
+            "jz" | "jnz" => Some(3),
+            _ => None,
+        };
+        let has_target = arity == Some(parts.len());
+        if let Some(target) = parts.last_mut().filter(|_| has_target) {
+            if target.parse::<isize>().is_err() && is_label(target) {
+                let idx = *labels
+                    .get(target.as_str())
+                    .ok_or_else(|| error(AsmErrorKind::UnknownLabel(target.clone())))?;
+                *target = (idx as isize - program.len() as isize).to_string();
+            }
+        }
+
+        let instr = Instruction::parse(&parts.join(" "))
+            .ok_or_else(|| error(AsmErrorKind::InvalidInstruction(text.to_string())))?;
+        program.push(instr);
+    }
+
+    Ok(program)
+}
+
+/// Turns instructions back into source text, inventing labels for every
+/// jump target inside the program. Jumps outside it keep their offsets.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let target = |idx: usize, offset: isize| idx.checked_add_signed(offset).filter(|&t| t < program.len());
+
+    let mut targets: Vec<usize> = program
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, instr)| match instr.op {
+            Op::Jmp(offset) | Op::Jz(_, offset) | Op::Jnz(_, offset) => target(idx, offset),
+            _ => None,
+        })
+        .collect();
+    targets.sort();
+    targets.dedup();
+
+    let label = |t: usize| format!("L{}", targets.binary_search(&t).expect("expected a label for target"));
+
+    let mut lines: Vec<String> = vec![];
+    for (idx, instr) in program.iter().enumerate() {
+        if targets.binary_search(&idx).is_ok() {
+            lines.push(format!("{}:", label(idx)));
+        }
+
+        let text = match instr.op {
+            Op::Jmp(offset) => target(idx, offset).map(|t| format!("jmp {}", label(t))),
+            Op::Jz(reg, offset) => target(idx, offset).map(|t| format!("jz {} {}", reg, label(t))),
+            Op::Jnz(reg, offset) => target(idx, offset).map(|t| format!("jnz {} {}", reg, label(t))),
+            _ => None,
+        };
+        lines.push(format!("    {}", text.unwrap_or_else(|| instr.to_string())));
+    }
+
+    lines.join("\n")
+}
+
+const NOOP: u8 = 0;
+const HALT: u8 = 1;
+const ADD: u8 = 2;
+const MUL: u8 = 3;
+const JMP: u8 = 4;
+const JZ: u8 = 5;
+const JNZ: u8 = 6;
+
+fn register_code(reg: Register) -> u8 {
+    match reg {
+        Register::X => 0,
+        Register::Y => 1,
+        Register::Z => 2,
+    }
+}
+
+/// Appends `n` as a zigzag-encoded LEB128 varint, so small values of either
+/// sign take a single byte.
+fn write_varint(out: &mut Vec<u8>, n: isize) {
+    let mut zigzag = ((n << 1) ^ (n >> (isize::BITS - 1))) as usize;
+    loop {
+        let byte = (zigzag & 0x7f) as u8;
+        zigzag >>= 7;
+        if zigzag == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Encodes a program as bytecode: `MAGIC`, then one opcode byte per
+/// instruction (the operation in the high bits and the register in the low
+/// two), followed by a varint immediate if the instruction takes one.
+pub fn encode(program: &[Instruction]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+
+    for instr in program {
+        let (opcode, reg, imm) = match instr.op {
+            Op::Noop => (NOOP, None, None),
+            Op::Halt => (HALT, None, None),
+            Op::Add(reg, n) => (ADD, Some(reg), Some(n)),
+            Op::Mul(reg, n) => (MUL, Some(reg), Some(n)),
+            Op::Jmp(offset) => (JMP, None, Some(offset)),
+            Op::Jz(reg, offset) => (JZ, Some(reg), Some(offset)),
+            Op::Jnz(reg, offset) => (JNZ, Some(reg), Some(offset)),
+        };
+
+        out.push(opcode << 2 | reg.map_or(0, register_code));
+        if let Some(imm) = imm {
+            write_varint(&mut out, imm);
+        }
+    }
+
+    out
+}
+
+/// The inverse of `encode`.
+pub fn decode(bytes: &[u8]) -> Result<Vec<Instruction>, DecodeError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(DecodeError { offset: 0, kind: DecodeErrorKind::BadMagic });
+    }
+
+    let mut program: Vec<Instruction> = vec![];
+    let mut pos = MAGIC.len();
+
+    while pos < bytes.len() {
+        let offset = pos;
+        let error = |kind| DecodeError { offset, kind };
+
+        let byte = bytes[pos];
+        pos += 1;
+
+        let reg = match byte & 0b11 {
+            0 => Register::X,
+            1 => Register::Y,
+            2 => Register::Z,
+            n => return Err(error(DecodeErrorKind::InvalidRegister(n))),
+        };
+
+        let mut read_varint = || -> Result<isize, DecodeError> {
+            let mut zigzag: usize = 0;
+            let mut shift = 0;
+            loop {
+                let byte = *bytes.get(pos).ok_or_else(|| error(DecodeErrorKind::Truncated))?;
+                pos += 1;
+                // Any bits that would be shifted out of the top don't fit
+                let bits = (byte & 0x7f) as usize;
+                if shift >= usize::BITS || bits.checked_shr(usize::BITS - shift).unwrap_or(0) != 0 {
+                    return Err(error(DecodeErrorKind::Overflow));
+                }
+                zigzag |= bits << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    return Ok((zigzag >> 1) as isize ^ -((zigzag & 1) as isize));
+                }
+            }
+        };
+
+        // Ops without a register are always encoded with zero register bits
+        let no_reg = || match byte & 0b11 {
+            0 => Ok(()),
+            n => Err(error(DecodeErrorKind::InvalidRegister(n))),
+        };
+
+        let op = match byte >> 2 {
+            NOOP => {
+                no_reg()?;
+                Op::Noop
+            }
+            HALT => {
+                no_reg()?;
+                Op::Halt
+            }
+            ADD => Op::Add(reg, read_varint()?),
+            MUL => Op::Mul(reg, read_varint()?),
+            JMP => {
+                no_reg()?;
+                Op::Jmp(read_varint()?)
+            }
+            JZ => Op::Jz(reg, read_varint()?),
+            JNZ => Op::Jnz(reg, read_varint()?),
+            _ => return Err(error(DecodeErrorKind::UnknownOpcode(byte))),
+        };
+        program.push(Instruction { op });
+    }
+
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "; multiply x by 2, y times\n\
+                          \x20   addy 3\n\
+                          loop:\n\
+                          \x20   mulx 2    # double it\n\
+                          \x20   addy -1\n\
+                          \x20   jnz y loop\n\
+                          done: halt";
+
+    #[test]
+    fn assembles_labels_and_comments() {
+        let program = assemble(SOURCE).unwrap();
+
+        assert_eq!(
+            program.iter().map(|i| i.op).collect::<Vec<Op>>(),
+            vec![
+                Op::Add(Register::Y, 3),
+                Op::Mul(Register::X, 2),
+                Op::Add(Register::Y, -1),
+                Op::Jnz(Register::Y, -2),
+                Op::Halt,
+            ]
+        );
+
+        let mut cpu = crate::CPU::init();
+        cpu.push_program(&program);
+        assert_eq!(cpu.last().map(|s| s.x), Some(8));
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let err = |source| assemble(source).unwrap_err();
+
+        assert_eq!(err("noop\n\nfoo 1"), AsmError { line: 3, kind: AsmErrorKind::InvalidInstruction("foo 1".to_string()) });
+        assert_eq!(err("noop\njmp nowhere"), AsmError { line: 2, kind: AsmErrorKind::UnknownLabel("nowhere".to_string()) });
+        assert_eq!(err("a: noop\na: noop"), AsmError { line: 2, kind: AsmErrorKind::DuplicateLabel("a".to_string()) });
+        assert_eq!(err("1a: noop"), AsmError { line: 1, kind: AsmErrorKind::InvalidLabel("1a".to_string()) });
+        assert_eq!(err("noop\njmp nowhere").to_string(), "line 2: unknown label `nowhere`");
+    }
+
+    #[test]
+    fn disassembles_with_labels() {
+        let program = assemble(SOURCE).unwrap();
+        let text = disassemble(&program);
+
+        assert_eq!(text, "    addy 3\nL0:\n    mulx 2\n    addy -1\n    jnz y L0\n    halt");
+        assert_eq!(assemble(&text).unwrap(), program);
+    }
+
+    #[test]
+    fn encodes_and_decodes() {
+        let mut program = assemble(SOURCE).unwrap();
+        program.push(Instruction { op: Op::Add(Register::Z, -1_000_000) });
+        program.push(Instruction { op: Op::Jmp(isize::MIN) });
+        program.push(Instruction { op: Op::Noop });
+
+        let bytes = encode(&program);
+        assert_eq!(&bytes[..4], MAGIC);
+        // addy 3 fits in an opcode byte and a single varint byte
+        assert_eq!(&bytes[4..6], &[ADD << 2 | 1, 6]);
+        assert_eq!(decode(&bytes).unwrap(), program);
+    }
+
+    #[test]
+    fn rejects_bad_bytecode() {
+        assert_eq!(decode(b"nope").unwrap_err().kind, DecodeErrorKind::BadMagic);
+        assert_eq!(decode(b"CPU\x01\x08").unwrap_err(), DecodeError { offset: 4, kind: DecodeErrorKind::Truncated });
+        assert_eq!(decode(b"CPU\x01\x03").unwrap_err().kind, DecodeErrorKind::InvalidRegister(3));
+        assert_eq!(decode(b"CPU\x01\xfc").unwrap_err().kind, DecodeErrorKind::UnknownOpcode(0xfc));
+        for op in [NOOP, HALT, JMP] {
+            let bytes = [b"CPU\x01".as_slice(), &[op << 2 | 2, 0]].concat();
+            assert_eq!(decode(&bytes).unwrap_err(), DecodeError { offset: 4, kind: DecodeErrorKind::InvalidRegister(2) });
+        }
+
+        // The tenth byte of a varint only has room for one more bit
+        let mut bytes = b"CPU\x01".to_vec();
+        bytes.push(ADD << 2);
+        bytes.extend([0xff; 9]);
+        bytes.push(0x02);
+        assert_eq!(decode(&bytes).unwrap_err(), DecodeError { offset: 4, kind: DecodeErrorKind::Overflow });
+
+        *bytes.last_mut().unwrap() = 0x01;
+        assert_eq!(decode(&bytes).unwrap(), vec![Instruction { op: Op::Add(Register::X, isize::MIN) }]);
+    }
+
+    #[test]
+    fn rejects_jumps_missing_operands() {
+        for source in ["jmp", "jz y", "jnz x"] {
+            assert_eq!(
+                assemble(source).unwrap_err().kind,
+                AsmErrorKind::InvalidInstruction(source.to_string())
+            );
+        }
+    }
+}
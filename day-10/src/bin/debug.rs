@@ -0,0 +1,136 @@
+use day_10::{assemble, Breakpoint, Debugger, Register, StopReason, CPU};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+};
+
+const HELP: &str = "commands:
+  break cycle <n> | break instr <n>    add a breakpoint
+  delete cycle <n> | delete instr <n>  remove a breakpoint
+  watch <x|y|z> | unwatch <x|y|z>      stop when a register changes
+  trace on | trace off                 print every cycle's state
+  step [n]                             advance n cycles (default 1)
+  continue                             run to the next stop
+  print                                show the current state
+  list                                 show the program around the pc
+  quit";
+
+fn parse_register(arg: Option<&str>) -> Option<Register> {
+    match arg {
+        Some("x") => Some(Register::X),
+        Some("y") => Some(Register::Y),
+        Some("z") => Some(Register::Z),
+        _ => None,
+    }
+}
+
+fn parse_breakpoint(kind: Option<&str>, n: Option<&str>) -> Option<Breakpoint> {
+    let n: usize = n?.parse().ok()?;
+    match kind? {
+        "cycle" => Some(Breakpoint::Cycle(n)),
+        "instr" => Some(Breakpoint::Instruction(n)),
+        _ => None,
+    }
+}
+
+fn flush_trace(dbg: &mut Debugger) {
+    for state in dbg.take_trace() {
+        println!("  {}", state);
+    }
+}
+
+// Usage: debug [<program>]
+// Loads an assembly program (the puzzle input by default) into a debugger.
+fn main() {
+    let source = match env::args().nth(1) {
+        Some(path) => fs::read_to_string(path).expect("could not read program"),
+        None => include_str!("../input.txt").to_string(),
+    };
+
+    let program = assemble(&source).unwrap_or_else(|e| panic!("{}", e));
+    let mut cpu = CPU::init();
+    cpu.push_program(&program);
+    let mut dbg = Debugger::new(cpu);
+
+    println!("loaded {} instructions, type help for commands", program.len());
+
+    let stdin = io::stdin();
+    loop {
+        print!("(debug) ");
+        io::stdout().flush().expect("could not flush stdout");
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("could not read stdin") == 0 {
+            break;
+        }
+        let mut parts = line.split_whitespace();
+
+        match parts.next() {
+            None => continue,
+            Some("quit" | "q") => break,
+            Some("help" | "h") => println!("{}", HELP),
+            Some("break" | "b") => match parse_breakpoint(parts.next(), parts.next()) {
+                Some(bp) => dbg.add_breakpoint(bp),
+                None => println!("usage: break cycle <n> | break instr <n>"),
+            },
+            Some("delete" | "d") => match parse_breakpoint(parts.next(), parts.next()) {
+                Some(bp) if dbg.remove_breakpoint(bp) => (),
+                Some(_) => println!("no such breakpoint"),
+                None => println!("usage: delete cycle <n> | delete instr <n>"),
+            },
+            Some("watch" | "w") => match parse_register(parts.next()) {
+                Some(reg) => dbg.watch(reg),
+                None => println!("usage: watch <x|y|z>"),
+            },
+            Some("unwatch") => match parse_register(parts.next()) {
+                Some(reg) => dbg.unwatch(reg),
+                None => println!("usage: unwatch <x|y|z>"),
+            },
+            Some("trace") => match parts.next() {
+                Some("on") => dbg.set_tracing(true),
+                Some("off") => dbg.set_tracing(false),
+                _ => println!("usage: trace on | trace off"),
+            },
+            Some("step" | "s") => {
+                let n: usize = parts.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+                for _ in 0..n {
+                    if dbg.step().is_none() {
+                        println!("halted");
+                        break;
+                    }
+                }
+                flush_trace(&mut dbg);
+                if let Some(state) = dbg.state() {
+                    println!("{}", state);
+                }
+            }
+            Some("continue" | "c") => {
+                let reason = dbg.resume();
+                flush_trace(&mut dbg);
+                match reason {
+                    StopReason::Breakpoint(bp) => println!("hit breakpoint {:?}", bp),
+                    StopReason::Watchpoint { register, old, new } => {
+                        println!("{} changed from {} to {}", register, old, new)
+                    }
+                    StopReason::Halted => println!("halted"),
+                }
+                if let Some(state) = dbg.state() {
+                    println!("{}", state);
+                }
+            }
+            Some("print" | "p") => match dbg.state() {
+                Some(state) => println!("{}", state),
+                None => println!("not started"),
+            },
+            Some("list" | "l") => {
+                let pc = dbg.state().map_or(0, |s| s.pc);
+                let program = dbg.cpu().program();
+                for (idx, instr) in program.iter().enumerate().skip(pc.saturating_sub(3)).take(7) {
+                    let marker = if idx == pc { "=>" } else { "  " };
+                    println!("{} {:>4}  {}", marker, idx, instr);
+                }
+            }
+            Some(cmd) => println!("unknown command `{}`, type help for commands", cmd),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::CPUState;
+use std::{fs::File, io, io::Write, path::Path};
+
+/// A screen drawn one pixel per cycle, left to right and top to bottom. A
+/// pixel is lit when the sprite, centred on the X register, covers the
+/// column being drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+    pixels: Vec<bool>,
+}
+
+impl Default for Crt {
+    /// The puzzle's 40×6 screen with a 3-pixel sprite
+    fn default() -> Self {
+        Self::new(40, 6, 3)
+    }
+}
+
+impl Crt {
+    /// Panics if the screen has no pixels. A zero-width sprite is allowed, it
+    /// just never lights anything.
+    pub fn new(width: usize, height: usize, sprite_width: usize) -> Self {
+        assert!(width > 0 && height > 0, "CRT must be at least 1×1, got {}×{}", width, height);
+
+        Self {
+            width,
+            height,
+            sprite_width,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn sprite_width(&self) -> usize {
+        self.sprite_width
+    }
+
+    pub fn pixels(&self) -> &[bool] {
+        &self.pixels
+    }
+
+    /// Whether the sprite covers `column` when X is `x`. With an even sprite
+    /// width the extra pixel goes to the right of X. Works in `i128` since X
+    /// can wrap around to anywhere in `isize`.
+    fn sprite_covers(&self, x: isize, column: usize) -> bool {
+        let left = x as i128 - (self.sprite_width as i128 - 1) / 2;
+        (left..left + self.sprite_width as i128).contains(&(column as i128))
+    }
+
+    /// Draws a single frame from the CPU's states, one pixel per cycle.
+    /// Drawing stops once the screen is full or the CPU runs out of
+    /// instructions, leaving any remaining pixels dark.
+    pub fn draw<I: IntoIterator<Item = CPUState>>(&mut self, states: I) {
+        self.pixels.fill(false);
+
+        for state in states.into_iter().take(self.pixels.len()) {
+            let pos = (state.cycle - 1) % self.pixels.len();
+            self.pixels[pos] = self.sprite_covers(state.x, pos % self.width);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        self.pixels
+            .chunks(self.width)
+            .map(|row| row.iter().map(|on| if *on { '#' } else { '.' }).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// One byte per image pixel, white where lit, with each screen pixel
+    /// repeated `scale` times across and down.
+    fn gray_pixels(&self, scale: usize) -> Vec<u8> {
+        self.pixels
+            .chunks(self.width)
+            .flat_map(|row| std::iter::repeat_n(row, scale))
+            .flat_map(|row| row.iter().flat_map(|on| std::iter::repeat_n(if *on { 255 } else { 0 }, scale)))
+            .collect()
+    }
+
+    /// The screen as a binary greyscale PGM at `scale` image pixels per
+    /// screen pixel.
+    pub fn to_pgm(&self, scale: usize) -> Vec<u8> {
+        let mut out = format!("P5\n{} {}\n255\n", self.width * scale, self.height * scale).into_bytes();
+        out.extend(self.gray_pixels(scale));
+        out
+    }
+
+    /// The same image as `to_pgm`, encoded as an 8-bit greyscale PNG.
+    pub fn write_png<W: Write>(&self, writer: W, scale: usize) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, (self.width * scale) as u32, (self.height * scale) as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.gray_pixels(scale))?;
+        writer.finish()
+    }
+
+    /// Saves the screen as an image, choosing PNG for a `.png` extension and
+    /// PGM for anything else.
+    pub fn save<P: AsRef<Path>>(&self, path: P, scale: usize) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut out = io::BufWriter::new(File::create(path)?);
+
+        if path.extension().is_some_and(|ext| ext == "png") {
+            self.write_png(&mut out, scale).map_err(io::Error::other)?;
+        } else {
+            out.write_all(&self.to_pgm(scale))?;
+        }
+        // Dropping a BufWriter flushes it but throws away any error
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_crt, CPU};
+
+    #[test]
+    fn draws_puzzle_screen() {
+        let mut cpu = CPU::init();
+        cpu.push_instr(include_str!("input.txt").trim());
+
+        let mut crt = Crt::default();
+        crt.draw(cpu);
+
+        assert_eq!(read_crt(crt.pixels()), Ok("EHBZLRJR".to_string()));
+    }
+
+    #[test]
+    fn custom_geometry() {
+        // X stays at 1 for four cycles, then moves to 5
+        let instrs = "noop\nnoop\naddx 4\nnoop\nnoop\nnoop";
+
+        let mut crt = Crt::new(4, 2, 1);
+        crt.draw(cpu_with(instrs));
+        assert_eq!(crt.render(), ".#..\n....");
+
+        let mut crt = Crt::new(8, 1, 2);
+        crt.draw(cpu_with(instrs));
+        assert_eq!(crt.render(), ".##..##.");
+    }
+
+    #[test]
+    fn draws_extreme_x() {
+        // X is 1 for the first two cycles, then isize::MAX, then wraps to
+        // isize::MIN
+        let mut crt = Crt::new(6, 1, 3);
+        crt.draw(cpu_with(&format!("addx {}\naddx 1\nnoop\nnoop", isize::MAX - 1)));
+
+        assert_eq!(crt.render(), "##....");
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1×1")]
+    fn rejects_empty_screen() {
+        Crt::new(0, 6, 3);
+    }
+
+    #[test]
+    fn scales_images() {
+        let mut crt = Crt::new(2, 1, 1);
+        crt.draw(cpu_with("noop"));
+
+        assert_eq!(crt.to_pgm(2), b"P5\n4 2\n255\n\x00\x00\xff\xff\x00\x00\xff\xff".to_vec());
+    }
+
+    fn cpu_with(instrs: &str) -> CPU {
+        let mut cpu = CPU::init();
+        cpu.push_instr(instrs);
+        cpu
+    }
+}
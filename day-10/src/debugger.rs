@@ -0,0 +1,191 @@
+use crate::{CPUState, Register, CPU};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop once the CPU reaches this cycle
+    Cycle(usize),
+    /// Stop whenever the instruction at this index starts executing
+    Instruction(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint(Breakpoint),
+    Watchpoint { register: Register, old: isize, new: isize },
+    /// The program ran off the end or hit `halt`
+    Halted,
+}
+
+/// Steps a `CPU` a cycle at a time, stopping at breakpoints and when watched
+/// registers change.
+///
+/// States reported here are the same as those yielded by the `CPU`
+/// iterator: register values *during* the given cycle.
+pub struct Debugger {
+    cpu: CPU,
+    breakpoints: Vec<Breakpoint>,
+    watched: Vec<Register>,
+    tracing: bool,
+    trace: Vec<CPUState>,
+    state: Option<CPUState>,
+}
+
+impl Debugger {
+    pub fn new(cpu: CPU) -> Self {
+        Self {
+            cpu,
+            breakpoints: vec![],
+            watched: vec![],
+            tracing: false,
+            trace: vec![],
+            state: None,
+        }
+    }
+
+    pub fn cpu(&self) -> &CPU {
+        &self.cpu
+    }
+
+    /// The most recent state, or None before the first step.
+    pub fn state(&self) -> Option<&CPUState> {
+        self.state.as_ref()
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|&b| b != breakpoint);
+        self.breakpoints.len() != before
+    }
+
+    pub fn watch(&mut self, register: Register) {
+        if !self.watched.contains(&register) {
+            self.watched.push(register);
+        }
+    }
+
+    pub fn unwatch(&mut self, register: Register) {
+        self.watched.retain(|&r| r != register);
+    }
+
+    /// While tracing, every state stepped through is recorded.
+    pub fn set_tracing(&mut self, tracing: bool) {
+        self.tracing = tracing;
+    }
+
+    pub fn trace(&self) -> &[CPUState] {
+        &self.trace
+    }
+
+    /// Takes the recorded trace, leaving it empty.
+    pub fn take_trace(&mut self) -> Vec<CPUState> {
+        std::mem::take(&mut self.trace)
+    }
+
+    /// Advances a single cycle, ignoring breakpoints and watchpoints.
+    pub fn step(&mut self) -> Option<&CPUState> {
+        let state = self.cpu.next()?;
+        if self.tracing {
+            self.trace.push(state.clone());
+        }
+        self.state = Some(state);
+        self.state.as_ref()
+    }
+
+    fn hit_breakpoint(&self, state: &CPUState) -> Option<Breakpoint> {
+        let starting = self.cpu.instr_cycle == 1;
+
+        self.breakpoints.iter().copied().find(|&b| match b {
+            Breakpoint::Cycle(cycle) => state.cycle == cycle,
+            Breakpoint::Instruction(idx) => starting && state.pc == idx,
+        })
+    }
+
+    /// Runs until a breakpoint or watchpoint triggers, or the CPU halts.
+    pub fn resume(&mut self) -> StopReason {
+        loop {
+            // Registers only change between cycles, so the CPU's current
+            // registers are the values during the last state
+            let before: Vec<isize> = self.watched.iter().map(|&r| self.cpu.registers()[r]).collect();
+
+            let Some(state) = self.step().cloned() else {
+                return StopReason::Halted;
+            };
+
+            let after = self.cpu.registers();
+            let changed = self.watched.iter().zip(before).find(|&(&r, old)| after[r] != old);
+            if let Some((&register, old)) = changed {
+                return StopReason::Watchpoint { register, old, new: after[register] };
+            }
+
+            if let Some(breakpoint) = self.hit_breakpoint(&state) {
+                return StopReason::Breakpoint(breakpoint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debugger(program: &str) -> Debugger {
+        let mut cpu = CPU::init();
+        cpu.push_instr(program);
+        Debugger::new(cpu)
+    }
+
+    #[test]
+    fn stops_at_cycle_breakpoints() {
+        let mut dbg = debugger("noop\naddx 3\naddx -5");
+        dbg.add_breakpoint(Breakpoint::Cycle(4));
+
+        assert_eq!(dbg.resume(), StopReason::Breakpoint(Breakpoint::Cycle(4)));
+        assert_eq!(dbg.state().map(|s| (s.cycle, s.x)), Some((4, 4)));
+        assert_eq!(dbg.resume(), StopReason::Halted);
+    }
+
+    #[test]
+    fn stops_at_instruction_breakpoints() {
+        let mut dbg = debugger("addy 2\naddx 1\naddy -1\njnz y -2");
+        dbg.add_breakpoint(Breakpoint::Instruction(1));
+
+        // The loop body starts twice: at cycle 3, then again after the jump
+        assert_eq!(dbg.resume(), StopReason::Breakpoint(Breakpoint::Instruction(1)));
+        assert_eq!(dbg.state().map(|s| s.cycle), Some(3));
+        assert_eq!(dbg.resume(), StopReason::Breakpoint(Breakpoint::Instruction(1)));
+        assert_eq!(dbg.state().map(|s| s.cycle), Some(8));
+        assert_eq!(dbg.resume(), StopReason::Halted);
+    }
+
+    #[test]
+    fn stops_at_watchpoints() {
+        let mut dbg = debugger("noop\naddx 3\naddx -5");
+        dbg.watch(Register::X);
+
+        assert_eq!(dbg.resume(), StopReason::Watchpoint { register: Register::X, old: 1, new: 4 });
+        assert_eq!(dbg.state().map(|s| s.cycle), Some(4));
+        assert_eq!(dbg.resume(), StopReason::Watchpoint { register: Register::X, old: 4, new: -1 });
+        assert_eq!(dbg.resume(), StopReason::Halted);
+    }
+
+    #[test]
+    fn traces_states() {
+        let mut dbg = debugger("noop\naddx 3\naddx -5");
+        dbg.set_tracing(true);
+        dbg.resume();
+
+        let xs: Vec<isize> = dbg.trace().iter().map(|s| s.x).collect();
+        assert_eq!(xs, vec![1, 1, 1, 4, 4, -1]);
+        assert_eq!(dbg.trace()[0].to_string(), "cycle    1  pc   0  x    1  y    0  z    0");
+    }
+}
@@ -1,56 +1,206 @@
-use std::collections::VecDeque;
+mod asm;
+mod crt;
+mod debugger;
+mod ocr;
+
+pub use asm::{assemble, decode, disassemble, encode, AsmError, AsmErrorKind, DecodeError, DecodeErrorKind};
+pub use crt::Crt;
+pub use debugger::{Breakpoint, Debugger, StopReason};
+pub use ocr::{read_crt, OcrError, OcrErrorKind, CRT_HEIGHT, CRT_WIDTH};
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    fn parse(str: &str) -> Option<Self> {
+        match str {
+            "x" => Some(Register::X),
+            "y" => Some(Register::Y),
+            "z" => Some(Register::Z),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::X => write!(f, "x"),
+            Register::Y => write!(f, "y"),
+            Register::Z => write!(f, "z"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Registers {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Index<Register> for Registers {
+    type Output = isize;
+
+    fn index(&self, register: Register) -> &isize {
+        match register {
+            Register::X => &self.x,
+            Register::Y => &self.y,
+            Register::Z => &self.z,
+        }
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut isize {
+        match register {
+            Register::X => &mut self.x,
+            Register::Y => &mut self.y,
+            Register::Z => &mut self.z,
+        }
+    }
+}
 
-#[derive(Clone, Copy)]
-enum Op {
-    AddX(isize),
+/// Jump offsets are relative to the jump instruction itself, so `jmp -1`
+/// loops on the previous instruction and `jmp 1` is a no-op.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Wraps around on overflow
+    Add(Register, isize),
+    /// Wraps around on overflow
+    Mul(Register, isize),
     Noop,
+    Jmp(isize),
+    /// Jump if the register is zero
+    Jz(Register, isize),
+    /// Jump if the register is not zero
+    Jnz(Register, isize),
+    Halt,
 }
 
-#[derive(Clone, Copy)]
-struct Instruction {
-    op: Op,
-    cycles: usize,
+/// How many cycles each kind of instruction takes to complete. The default
+/// matches the puzzle, where `addx` takes two cycles and `noop` one. Every
+/// instruction takes at least one cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleCosts {
+    pub add: usize,
+    pub mul: usize,
+    pub noop: usize,
+    pub jmp: usize,
+    pub branch: usize,
+    pub halt: usize,
+}
+
+impl Default for CycleCosts {
+    fn default() -> Self {
+        Self {
+            add: 2,
+            mul: 2,
+            noop: 1,
+            jmp: 1,
+            branch: 1,
+            halt: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub op: Op,
 }
 
 impl Instruction {
-    fn parse(str: &str) -> Option<Self> {
-        if str.starts_with("addx") {
-            let n = str
-                .split(" ")
-                .last()
-                .expect("expect last part of add instr")
-                .parse::<isize>()
-                .expect("expect to be numeric");
-            Some(Self {
-                op: Op::AddX(n),
-                cycles: 2,
-            })
-        } else if str.starts_with("noop") {
-            Some(Self {
-                op: Op::Noop,
-                cycles: 1,
-            })
-        } else {
-            None
+    pub fn parse(str: &str) -> Option<Self> {
+        let mut parts = str.split_whitespace();
+        let name = parts.next()?;
+        let args: Vec<&str> = parts.collect();
+        let num = |arg: &str| arg.parse::<isize>().ok();
+
+        let op = match (name, args.as_slice()) {
+            ("noop", []) => Op::Noop,
+            ("halt", []) => Op::Halt,
+            ("jmp", [offset]) => Op::Jmp(num(offset)?),
+            ("jz", [reg, offset]) => Op::Jz(Register::parse(reg)?, num(offset)?),
+            ("jnz", [reg, offset]) => Op::Jnz(Register::parse(reg)?, num(offset)?),
+            (name, [n]) if name.starts_with("add") => Op::Add(Register::parse(&name[3..])?, num(n)?),
+            (name, [n]) if name.starts_with("mul") => Op::Mul(Register::parse(&name[3..])?, num(n)?),
+            _ => return None,
+        };
+
+        Some(Self { op })
+    }
+
+    pub fn cycles(&self, costs: &CycleCosts) -> usize {
+        match self.op {
+            Op::Add(..) => costs.add,
+            Op::Mul(..) => costs.mul,
+            Op::Noop => costs.noop,
+            Op::Jmp(_) => costs.jmp,
+            Op::Jz(..) | Op::Jnz(..) => costs.branch,
+            Op::Halt => costs.halt,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            Op::Add(reg, n) => write!(f, "add{} {}", reg, n),
+            Op::Mul(reg, n) => write!(f, "mul{} {}", reg, n),
+            Op::Noop => write!(f, "noop"),
+            Op::Jmp(offset) => write!(f, "jmp {}", offset),
+            Op::Jz(reg, offset) => write!(f, "jz {} {}", reg, offset),
+            Op::Jnz(reg, offset) => write!(f, "jnz {} {}", reg, offset),
+            Op::Halt => write!(f, "halt"),
         }
     }
 }
 
 pub struct CPU {
     cycle: usize,
-    x: isize,
-    q: VecDeque<Instruction>,
-    curr_instr: Option<Instruction>,
+    registers: Registers,
+    program: Vec<Instruction>,
+    pc: usize,
     instr_cycle: usize,
+    costs: CycleCosts,
+    halted: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CPUState {
+    /// Register values during this cycle, which is what the puzzle samples
     pub x: isize,
+    pub y: isize,
+    pub z: isize,
+    /// Register values once this cycle has finished, after any instruction
+    /// completing on it has taken effect
+    pub end: Registers,
     pub cycle: usize,
+    /// Index of the instruction executing during this cycle, or past the end
+    /// of the program once it has finished
+    pub pc: usize,
+}
+
+impl fmt::Display for CPUState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cycle {:>4}  pc {:>3}  x {:>4}  y {:>4}  z {:>4}", self.cycle, self.pc, self.x, self.y, self.z)
+    }
 }
 
 impl CPUState {
+    pub fn during(&self) -> Registers {
+        Registers { x: self.x, y: self.y, z: self.z }
+    }
+
+    /// The cycle number times the value of X during the cycle
     pub fn signal_strength(&self) -> isize {
         self.x * (self.cycle as isize)
     }
@@ -58,33 +208,62 @@ impl CPUState {
 
 impl CPU {
     pub fn init() -> Self {
+        Self::with_costs(CycleCosts::default())
+    }
+
+    /// Panics if any instruction costs zero cycles.
+    pub fn with_costs(costs: CycleCosts) -> Self {
+        let CycleCosts { add, mul, noop, jmp, branch, halt } = costs;
+        assert!(
+            [add, mul, noop, jmp, branch, halt].iter().all(|&cost| cost > 0),
+            "instructions must take at least one cycle: {:?}",
+            costs
+        );
+
         Self {
             cycle: 0,
-            x: 1,
-            q: VecDeque::new(),
-            curr_instr: None,
+            registers: Registers { x: 1, y: 0, z: 0 },
+            program: vec![],
+            pc: 0,
             instr_cycle: 0,
+            costs,
+            halted: false,
         }
     }
 
     pub fn push_instr(&mut self, input: &str) {
-        self.q.extend(
+        self.program.extend(
             input
-                .split("\n")
+                .split('\n')
                 .map(Instruction::parse)
                 .map(Option::unwrap),
         );
+    }
+
+    pub fn push_program(&mut self, program: &[Instruction]) {
+        self.program.extend_from_slice(program);
+    }
 
-        if self.curr_instr.is_none() {
-            self.curr_instr = self.q.pop_front();
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.registers
+    }
+
+    fn curr_instr(&self) -> Option<Instruction> {
+        if self.halted {
+            None
+        } else {
+            self.program.get(self.pc).copied()
         }
     }
 
     fn tick(&mut self) {
-        if let Some(instr) = self.curr_instr {
-            if instr.cycles == self.instr_cycle {
+        if let Some(instr) = self.curr_instr() {
+            if instr.cycles(&self.costs) == self.instr_cycle {
                 self.exec(&instr);
-                self.curr_instr = self.q.pop_front();
                 self.instr_cycle = 0;
             }
         }
@@ -93,49 +272,99 @@ impl CPU {
         self.cycle += 1;
     }
 
-    fn exec(&mut self, instr: &Instruction) {
-        match instr.op {
-            Op::AddX(n) => self.x += n,
-            Op::Noop => (),
+    /// The registers and next instruction index after executing `instr`,
+    /// without applying them.
+    fn execute(&self, instr: &Instruction) -> (Registers, Option<usize>) {
+        let mut registers = self.registers;
+        let jump = |offset: isize| self.pc.checked_add_signed(offset);
+
+        let next_pc = match instr.op {
+            Op::Add(reg, n) => {
+                registers[reg] = registers[reg].wrapping_add(n);
+                Some(self.pc + 1)
+            }
+            Op::Mul(reg, n) => {
+                registers[reg] = registers[reg].wrapping_mul(n);
+                Some(self.pc + 1)
+            }
+            Op::Noop => Some(self.pc + 1),
+            Op::Jmp(offset) => jump(offset),
+            Op::Jz(reg, offset) if registers[reg] == 0 => jump(offset),
+            Op::Jnz(reg, offset) if registers[reg] != 0 => jump(offset),
+            Op::Jz(..) | Op::Jnz(..) => Some(self.pc + 1),
+            Op::Halt => None,
         };
+
+        (registers, next_pc)
+    }
+
+    fn exec(&mut self, instr: &Instruction) {
+        let (registers, next_pc) = self.execute(instr);
+        self.registers = registers;
+
+        // Jumping before the start of the program halts, like running off
+        // the end
+        match next_pc {
+            Some(pc) => self.pc = pc,
+            None => self.halted = true,
+        }
     }
 }
 
+/// Yields one state per cycle. An instruction's effects are applied at the
+/// start of the cycle after it completes, so `x`, `y` and `z` hold the values
+/// during the cycle and `end` holds them once it's over. A final state is
+/// yielded for the cycle after the last instruction completes.
 impl Iterator for CPU {
     type Item = CPUState;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.curr_instr.is_none() {
-            return None;
-        };
+        self.curr_instr()?;
 
         self.tick();
-        let x = self.x;
+        let Registers { x, y, z } = self.registers;
+        let end = match self.curr_instr() {
+            Some(instr) if instr.cycles(&self.costs) == self.instr_cycle => self.execute(&instr).0,
+            _ => self.registers,
+        };
 
         Some(CPUState {
             x,
+            y,
+            z,
+            end,
             cycle: self.cycle,
+            pc: self.pc,
         })
     }
 }
 
-pub fn to_crt_output_buffer(cpu: CPU) -> Vec<bool> {
-    cpu.map(|state| {
-        dbg!(&state);
-        dbg!(state.x.abs_diff(((state.cycle - 1) % 40) as isize) < 2)
-    })
-    .collect()
+/// The cycles the puzzle samples the signal strength on
+pub fn puzzle_sample_cycles() -> impl Iterator<Item = usize> {
+    (20..=220).step_by(40)
 }
 
-pub fn print_crt(output: &Vec<bool>) {
-    output.chunks(40).for_each(|chunk| {
-        let line: String = chunk
-            .into_iter()
-            .map(|on_or_off| if *on_or_off { '#' } else { '.' })
-            .collect();
+/// Runs the CPU just far enough to sample the signal strength on each of
+/// `cycles`, returning `(cycle, strength)` pairs in cycle order. Cycles the
+/// program never reaches are left out.
+pub fn sample_signal_strengths<I: IntoIterator<Item = usize>>(cpu: CPU, cycles: I) -> Vec<(usize, isize)> {
+    let cycles: BTreeSet<usize> = cycles.into_iter().collect();
+    let last = match cycles.last() {
+        Some(last) => *last,
+        None => return vec![],
+    };
+
+    cpu.take_while(|state| state.cycle <= last)
+        .filter(|state| cycles.contains(&state.cycle))
+        .map(|state| (state.cycle, state.signal_strength()))
+        .collect()
+}
 
-        println!("{}", line);
-    });
+/// The pixels of the puzzle's screen, drawn by `Crt::default()`.
+pub fn to_crt_output_buffer(cpu: CPU) -> Vec<bool> {
+    let mut crt = Crt::default();
+    crt.draw(cpu);
+    crt.pixels().to_vec()
 }
 
 #[cfg(test)]
@@ -169,6 +398,32 @@ mod tests {
             .sum();
 
         assert_eq!(strength, 13140);
+
+        let mut cpu = CPU::init();
+        cpu.push_instr(instrs);
+
+        assert_eq!(
+            sample_signal_strengths(cpu, puzzle_sample_cycles()),
+            vec![(20, 420), (60, 1140), (100, 1800), (140, 2940), (180, 2880), (220, 3960)]
+        );
+    }
+
+    #[test]
+    fn during_and_end_of_cycle() {
+        let mut cpu = CPU::init();
+        cpu.push_instr("noop\naddx 3\naddx -5");
+
+        let values: Vec<(isize, isize)> = cpu.map(|s| (s.during().x, s.end.x)).collect();
+        assert_eq!(values, vec![(1, 1), (1, 1), (1, 4), (4, 4), (4, -1), (-1, -1)]);
+    }
+
+    #[test]
+    fn samples_arbitrary_cycles() {
+        let mut cpu = CPU::init();
+        cpu.push_instr("noop\naddx 3\naddx -5");
+
+        // Out of order, duplicated and past the end of the program
+        assert_eq!(sample_signal_strengths(cpu, [5, 1, 5, 100]), vec![(1, 1), (5, 20)]);
     }
 
     #[test]
@@ -178,8 +433,87 @@ mod tests {
         let mut cpu = CPU::init();
         cpu.push_instr(instrs);
 
-        let crt_output = to_crt_output_buffer(cpu);
+        let mut crt = Crt::default();
+        crt.draw(cpu);
+
+        assert_eq!(
+            crt.render(),
+            "##..##..##..##..##..##..##..##..##..##..\n\
+             ###...###...###...###...###...###...###.\n\
+             ####....####....####....####....####....\n\
+             #####.....#####.....#####.....#####.....\n\
+             ######......######......######......####\n\
+             #######.......#######.......#######....."
+        );
+
+        let mut cpu = CPU::init();
+        cpu.push_instr(instrs);
+        assert_eq!(to_crt_output_buffer(cpu), crt.pixels());
+    }
+
+    #[test]
+    fn parses_extended_instructions() {
+        assert_eq!(Instruction::parse("addx -5").map(|i| i.op), Some(Op::Add(Register::X, -5)));
+        assert_eq!(Instruction::parse("addy 3").map(|i| i.op), Some(Op::Add(Register::Y, 3)));
+        assert_eq!(Instruction::parse("mulz 2").map(|i| i.op), Some(Op::Mul(Register::Z, 2)));
+        assert_eq!(Instruction::parse("jnz y -2").map(|i| i.op), Some(Op::Jnz(Register::Y, -2)));
+        assert_eq!(Instruction::parse("halt").map(|i| i.op), Some(Op::Halt));
+        assert_eq!(Instruction::parse("addw 1"), None);
+        assert_eq!(Instruction::parse("jmp"), None);
+    }
+
+    #[test]
+    fn loops_and_halts() {
+        // x = 1 * 2 * 2 * 2, counting y down from 3
+        let instrs = "addy 3\n\
+                      mulx 2\n\
+                      addy -1\n\
+                      jnz y -2\n\
+                      halt\n\
+                      addx 100";
+
+        let mut cpu = CPU::init();
+        cpu.push_instr(instrs);
+        let last = cpu.last().unwrap();
+
+        assert_eq!(last.x, 8);
+        assert_eq!(last.y, 0);
+        // 2 for addy, then three loops of mulx (2), addy (2) and jnz (1),
+        // then halt (1), plus the cycle after halting
+        assert_eq!(last.cycle, 2 + 3 * 5 + 1 + 1);
+    }
+
+    #[test]
+    fn custom_cycle_costs() {
+        let costs = CycleCosts { add: 1, ..CycleCosts::default() };
+        let mut cpu = CPU::with_costs(costs);
+        cpu.push_instr("noop\naddx 3\naddx -5");
+
+        let output: Vec<isize> = cpu.map(|s| s.x).collect();
+        assert_eq!(output, vec![1, 1, 4, -1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one cycle")]
+    fn rejects_zero_costs() {
+        CPU::with_costs(CycleCosts { noop: 0, ..CycleCosts::default() });
+    }
+
+    #[test]
+    fn registers_wrap() {
+        let mut cpu = CPU::init();
+        cpu.push_instr(&format!("addx {}\naddx 1\nmulx 2", isize::MAX - 1));
+
+        // x reaches isize::MAX, wraps to isize::MIN, then doubles to 0
+        assert_eq!(cpu.last().unwrap().x, 0);
+    }
+
+    #[test]
+    fn jz_skips() {
+        let mut cpu = CPU::init();
+        cpu.push_instr("jz y 2\naddx 10\naddz 1");
 
-        print_crt(&crt_output);
+        let last = cpu.last().unwrap();
+        assert_eq!((last.x, last.z), (1, 1));
     }
 }
@@ -1,19 +1,52 @@
-use day_10::{print_crt, to_crt_output_buffer, CPU};
+use day_10::{puzzle_sample_cycles, read_crt, sample_signal_strengths, Crt, CPU, CRT_WIDTH};
+use std::env;
 
 fn main() {
     let contents = include_str!("input.txt").trim();
     let mut cpu = CPU::init();
     cpu.push_instr(contents);
 
-    let strength: isize = cpu
-        .filter(|s| (s.cycle + 20) % 40 == 0)
-        .map(|s| s.signal_strength())
+    let strength: isize = sample_signal_strengths(cpu, puzzle_sample_cycles())
+        .iter()
+        .map(|(_, strength)| strength)
         .sum();
 
     println!("{}", strength);
 
     // Part 2
+    // Usage: day-10 [--width <n>] [--height <n>] [--sprite <n>] [--image <path>] [--scale <n>]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arg_value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("Expected a value after {}", flag)))
+    };
+    let numeric = |flag: &str, default: usize| {
+        arg_value(flag).map_or(default, |n| n.parse().unwrap_or_else(|_| panic!("Expected {} to be numeric", flag)))
+    };
+
+    let default = Crt::default();
+    let mut crt = Crt::new(
+        numeric("--width", default.width()),
+        numeric("--height", default.height()),
+        numeric("--sprite", default.sprite_width()),
+    );
+
     let mut cpu_2 = CPU::init();
     cpu_2.push_instr(contents);
-    print_crt(&to_crt_output_buffer(cpu_2));
+    crt.draw(cpu_2);
+    println!("{}", crt.render());
+
+    // The font only makes sense at the puzzle's width
+    if crt.width() == CRT_WIDTH {
+        match read_crt(crt.pixels()) {
+            Ok(letters) => println!("{}", letters),
+            Err(err) => eprintln!("Couldn't read the CRT: {}", err),
+        }
+    }
+
+    if let Some(path) = arg_value("--image") {
+        crt.save(path, numeric("--scale", 8)).expect("Could not write image");
+        println!("wrote {}", path);
+    }
 }
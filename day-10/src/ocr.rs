@@ -0,0 +1,141 @@
+use std::fmt;
+
+pub const CRT_WIDTH: usize = 40;
+pub const CRT_HEIGHT: usize = 6;
+
+const GLYPH_WIDTH: usize = 4;
+/// Each glyph is followed by a blank column
+const GLYPH_SPACING: usize = GLYPH_WIDTH + 1;
+
+/// The 4×6 font the puzzle draws its letters in, one row per line. Letters
+/// the font draws five columns wide, like Y, don't fit a cell and are left out.
+const FONT: &[(char, [&str; CRT_HEIGHT])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrErrorKind {
+    /// The buffer holds fewer than a full screen of pixels
+    TooShort(usize),
+    /// The glyph, drawn as rows of `#`/`.`, isn't in the font
+    UnknownGlyph(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrError {
+    /// 0-based position of the letter on the screen
+    pub letter: usize,
+    pub kind: OcrErrorKind,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            OcrErrorKind::TooShort(len) => write!(
+                f,
+                "expected at least {} pixels, got {}",
+                CRT_WIDTH * CRT_HEIGHT,
+                len
+            ),
+            OcrErrorKind::UnknownGlyph(rows) => {
+                write!(f, "letter {}: unrecognised glyph", self.letter)?;
+                rows.iter().try_for_each(|row| write!(f, "\n{}", row))
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+fn glyph_rows(output: &[bool], letter: usize) -> Vec<String> {
+    (0..CRT_HEIGHT)
+        .map(|row| {
+            let start = row * CRT_WIDTH + letter * GLYPH_SPACING;
+            output[start..start + GLYPH_WIDTH]
+                .iter()
+                .map(|on| if *on { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads the letters drawn on a 40×6 CRT buffer, as produced by
+/// `to_crt_output_buffer`. Any pixels past the end of the screen are ignored.
+pub fn read_crt(output: &[bool]) -> Result<String, OcrError> {
+    if output.len() < CRT_WIDTH * CRT_HEIGHT {
+        return Err(OcrError {
+            letter: 0,
+            kind: OcrErrorKind::TooShort(output.len()),
+        });
+    }
+
+    (0..CRT_WIDTH / GLYPH_SPACING)
+        .map(|letter| {
+            let rows = glyph_rows(output, letter);
+            FONT.iter()
+                .find(|(_, glyph)| glyph.iter().zip(&rows).all(|(a, b)| a == b))
+                .map(|(c, _)| *c)
+                .ok_or(OcrError {
+                    letter,
+                    kind: OcrErrorKind::UnknownGlyph(rows),
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_crt_output_buffer, CPU};
+
+    fn draw(rows: &[&str]) -> Vec<bool> {
+        rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect()
+    }
+
+    #[test]
+    fn reads_letters() {
+        let output = draw(&[
+            "####.#..#.###..####.#....###....##.###..",
+            "#....#..#.#..#....#.#....#..#....#.#..#.",
+            "###..####.###....#..#....#..#....#.#..#.",
+            "#....#..#.#..#..#...#....###.....#.###..",
+            "#....#..#.#..#.#....#....#.#..#..#.#.#..",
+            "####.#..#.###..####.####.#..#..##..#..#.",
+        ]);
+
+        assert_eq!(read_crt(&output), Ok("EHBZLRJR".to_string()));
+    }
+
+    #[test]
+    fn reads_puzzle_input() {
+        let mut cpu = CPU::init();
+        cpu.push_instr(include_str!("input.txt").trim());
+
+        assert_eq!(read_crt(&to_crt_output_buffer(cpu)), Ok("EHBZLRJR".to_string()));
+    }
+
+    #[test]
+    fn rejects_unknown_glyphs() {
+        let mut output = draw(&["#".repeat(CRT_WIDTH).as_str(); CRT_HEIGHT]);
+        assert_eq!(read_crt(&output).unwrap_err().letter, 0);
+
+        output.truncate(CRT_WIDTH);
+        assert_eq!(read_crt(&output).unwrap_err().kind, OcrErrorKind::TooShort(CRT_WIDTH));
+    }
+}
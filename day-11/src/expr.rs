@@ -0,0 +1,238 @@
+use crate::WorryLevel;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// The right hand side of a monkey's `Operation: new = ...` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Num(WorryLevel),
+    BinOp(Box<Expr>, BinOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    NumberTooLarge,
+    UnclosedParen,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprError {
+    /// 0-based character offset into the expression
+    pub column: usize,
+    pub kind: ExprErrorKind,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: ", self.column)?;
+        match &self.kind {
+            ExprErrorKind::UnexpectedChar(c) => write!(f, "unexpected `{}`", c),
+            ExprErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ExprErrorKind::NumberTooLarge => write!(f, "number is too large"),
+            ExprErrorKind::UnclosedParen => write!(f, "expected `)`"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// Recursive descent over the usual grammar, with `*` and `/` binding
+/// tighter than `+` and `-`, and all four associating to the left:
+///
+/// ```text
+/// expr = term (("+" | "-") term)*
+/// term = atom (("*" | "/") atom)*
+/// atom = "old" | number | "(" expr ")"
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, kind: ExprErrorKind) -> ExprError {
+        ExprError { column: self.pos, kind }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => BinOp::Add,
+                Some('-') => BinOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::BinOp(Box::new(lhs), op, Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.atom()?;
+        loop {
+            let op = match self.peek() {
+                Some('*') => BinOp::Mul,
+                Some('/') => BinOp::Div,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::BinOp(Box::new(lhs), op, Box::new(self.atom()?));
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, ExprError> {
+        match self.peek() {
+            None => Err(self.error(ExprErrorKind::UnexpectedEnd)),
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                if self.eat(')') {
+                    Ok(inner)
+                } else {
+                    Err(self.error(ExprErrorKind::UnclosedParen))
+                }
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let rest = &self.input[self.pos..];
+                let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                let num = rest[..len]
+                    .parse()
+                    .map_err(|_| self.error(ExprErrorKind::NumberTooLarge))?;
+                self.pos += len;
+                Ok(Expr::Num(num))
+            }
+            Some(_) if self.input[self.pos..].starts_with("old") => {
+                self.pos += 3;
+                Ok(Expr::Old)
+            }
+            Some(c) => Err(self.error(ExprErrorKind::UnexpectedChar(c))),
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Self, ExprError> {
+        let mut parser = Parser { input, pos: 0 };
+        let expr = parser.expr()?;
+
+        match parser.peek() {
+            None => Ok(expr),
+            Some(c) => Err(parser.error(ExprErrorKind::UnexpectedChar(c))),
+        }
+    }
+
+    pub fn uses_division(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Num(_) => false,
+            Expr::BinOp(_, BinOp::Div, _) => true,
+            Expr::BinOp(lhs, _, rhs) => lhs.uses_division() || rhs.uses_division(),
+        }
+    }
+
+    /// Evaluates the expression with `old` set to `old`. With a modulus every
+    /// intermediate result is reduced into `0..modulus`, so worry levels
+    /// can't overflow; that only preserves divisibility for `+`, `-` and `*`.
+    ///
+    /// Worry levels are signed, so subtracting below zero gives a negative
+    /// level. Division rounds towards zero, and dividing by zero gives zero.
+    /// Panics if a worry level overflows.
+    pub fn eval(&self, old: WorryLevel, modulus: Option<WorryLevel>) -> WorryLevel {
+        let reduce = |n: WorryLevel| modulus.map_or(n, |m| n.rem_euclid(m));
+
+        match self {
+            Expr::Old => reduce(old),
+            Expr::Num(n) => reduce(*n),
+            Expr::BinOp(lhs, op, rhs) => {
+                let (a, b) = (lhs.eval(old, modulus), rhs.eval(old, modulus));
+                let result = match op {
+                    BinOp::Add => a.checked_add(b),
+                    BinOp::Sub => a.checked_sub(b),
+                    BinOp::Mul => a.checked_mul(b),
+                    BinOp::Div if b == 0 => Some(0),
+                    BinOp::Div => a.checked_div(b),
+                };
+
+                reduce(result.expect("worry level overflowed"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence_and_parens() {
+        let expr = Expr::parse("old * 2 + 1").unwrap();
+        assert_eq!(expr.eval(5, None), 11);
+
+        let expr = Expr::parse("old * (2 + 1)").unwrap();
+        assert_eq!(expr.eval(5, None), 15);
+
+        let expr = Expr::parse("100 - old - 10 / 2").unwrap();
+        assert_eq!(expr.eval(5, None), 90);
+    }
+
+    #[test]
+    fn old_on_either_side() {
+        assert_eq!(Expr::parse("old + old").unwrap().eval(7, None), 14);
+        assert_eq!(Expr::parse("3*old").unwrap().eval(7, None), 21);
+    }
+
+    #[test]
+    fn reduces_by_modulus() {
+        let expr = Expr::parse("old * old - 5").unwrap();
+
+        assert_eq!(expr.eval(3, Some(7)), 4);
+        assert_eq!(expr.eval(1, Some(7)), 3);
+        assert!(!expr.uses_division());
+        assert!(Expr::parse("(old + 1) / 2").unwrap().uses_division());
+    }
+
+    #[test]
+    fn below_zero_and_division_by_zero() {
+        assert_eq!(Expr::parse("old - 10").unwrap().eval(4, None), -6);
+        assert_eq!(Expr::parse("old - 10").unwrap().eval(4, Some(7)), 1);
+        assert_eq!(Expr::parse("old / (old - old)").unwrap().eval(4, None), 0);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = |input| Expr::parse(input).unwrap_err();
+
+        assert_eq!(error("old +").kind, ExprErrorKind::UnexpectedEnd);
+        assert_eq!(error("old ^ 2"), ExprError { column: 4, kind: ExprErrorKind::UnexpectedChar('^') });
+        assert_eq!(error("(old + 1"), ExprError { column: 8, kind: ExprErrorKind::UnclosedParen });
+        assert_eq!(error("old 2").kind, ExprErrorKind::UnexpectedChar('2'));
+    }
+}
@@ -1,26 +1,25 @@
+mod expr;
+
+pub use expr::{BinOp, Expr, ExprError, ExprErrorKind};
+
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
 lazy_static! {
     static ref MONKEY_REGEX: Regex = Regex::new(
-        r"^Monkey (?P<id>\d+):[\r\n].*items: (?P<items>[\d, ]+)[\r\n].*old (?P<operation>[*+].+)[\r\n].*divisible by (?P<divisible_by>\d+)[\r\n].*monkey (?P<condtrue>\d+)[\r\n].*monkey (?P<condfalse>\d+)"
+        r"^Monkey (?P<id>\d+):[\r\n].*items: (?P<items>[\d, ]+)[\r\n].*new = (?P<operation>.+)[\r\n].*divisible by (?P<divisible_by>\d+)[\r\n].*monkey (?P<condtrue>\d+)[\r\n].*monkey (?P<condfalse>\d+)"
     )
     .unwrap();
 }
 
-type WorryLevel = usize;
-
-#[derive(Debug)]
-enum OpType {
-    Add(WorryLevel),
-    Multiply(WorryLevel),
-    Square,
-}
+/// Signed, so operations can take worry below zero.
+pub type WorryLevel = i64;
 
 #[derive(Debug)]
 struct ThrowDecision {
-    divisible_by: usize,
+    divisible_by: WorryLevel,
     if_true: usize,
     if_false: usize,
 }
@@ -39,7 +38,7 @@ impl ThrowDecision {
 pub struct Monkey {
     num_inspections: usize,
     items: VecDeque<WorryLevel>,
-    operation: OpType,
+    operation: Expr,
     decision: ThrowDecision,
 }
 
@@ -62,28 +61,14 @@ impl Monkey {
             .expect("expected operation")
             .as_str();
 
-        let operation = if op_string.starts_with("* old") {
-            OpType::Square
-        } else {
-            let operand = op_string
-                .split(" ")
-                .last()
-                .expect("expected operand")
-                .parse::<usize>()
-                .expect("expected numeric operand");
-
-            if op_string.starts_with("*") {
-                OpType::Multiply(operand)
-            } else {
-                OpType::Add(operand)
-            }
-        };
+        let operation = Expr::parse(op_string)
+            .unwrap_or_else(|err| panic!("invalid operation `{}`: {}", op_string, err));
 
         let divisible_by = captures
             .name("divisible_by")
             .expect("expected divisible_by")
             .as_str()
-            .parse::<usize>()
+            .parse::<WorryLevel>()
             .expect("expected numerical divisible_by");
 
         let if_true = captures
@@ -112,7 +97,7 @@ impl Monkey {
         }
     }
 
-    fn throw_queue(&self, worry_modulus: Option<usize>) -> Vec<(WorryLevel, usize)> {
+    fn throw_queue(&self, worry_modulus: Option<WorryLevel>) -> Vec<(WorryLevel, usize)> {
         self.items.iter().map(|item| {
             let new_item = self.alter(*item, worry_modulus);
             let new_monkey = self.decision.check(new_item);
@@ -121,15 +106,11 @@ impl Monkey {
         }).collect()
     }
 
-    fn alter(&self, item: WorryLevel, worry_modulus: Option<usize>) -> WorryLevel {
-        let new_level = match self.operation {
-            OpType::Square => item * item,
-            OpType::Add(n) => item + n,
-            OpType::Multiply(n) => item * n,
-        };
+    fn alter(&self, item: WorryLevel, worry_modulus: Option<WorryLevel>) -> WorryLevel {
+        let new_level = self.operation.eval(item, worry_modulus);
 
-        if let Some(n) = worry_modulus {
-            new_level.rem_euclid(n)
+        if worry_modulus.is_some() {
+            new_level
         } else {
             new_level / 3
         }
@@ -140,27 +121,82 @@ impl Monkey {
     }
 }
 
+/// The rounds an item spends before its (monkey, worry) state at the start of
+/// a round repeats, with the running inspection counts it contributes.
+struct ItemCycle {
+    /// `counts[r]` is the inspections per monkey over the first `r` rounds
+    counts: Vec<Vec<usize>>,
+    /// The round the repeated state was first seen
+    start: usize,
+    len: usize,
+}
+
+impl ItemCycle {
+    /// Extrapolated counts can exceed a `usize` long before `rounds` does, so
+    /// they're widened to `u128`.
+    fn counts_after(&self, rounds: usize) -> Vec<u128> {
+        if rounds < self.counts.len() {
+            return self.counts[rounds].iter().map(|&n| n as u128).collect();
+        }
+
+        let cycles = ((rounds - self.start) / self.len) as u128;
+        let remainder = (rounds - self.start) % self.len;
+        let per_cycle = self.counts[self.start + self.len].iter().zip(&self.counts[self.start]);
+
+        self.counts[self.start + remainder]
+            .iter()
+            .zip(per_cycle)
+            .map(|(&count, (end, start))| count as u128 + cycles * (end - start) as u128)
+            .collect()
+    }
+}
+
+/// Reducing worry levels by the product of the divisors only preserves
+/// divisibility through addition, subtraction and multiplication, so the
+/// worry modulus can't be combined with an operation that divides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModulusError {
+    /// The first monkey whose operation divides
+    pub monkey: usize,
+}
+
+impl fmt::Display for ModulusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "monkey {}: can't use a worry modulus with an operation that divides", self.monkey)
+    }
+}
+
+impl std::error::Error for ModulusError {}
+
 pub struct MonkeyInTheMiddle {
     pub round: usize,
-    pub worry_modulus: Option<usize>,
+    pub worry_modulus: Option<WorryLevel>,
     pub monkeys: Vec<Monkey>,
 }
 
 impl MonkeyInTheMiddle {
+    /// Panics if the script can't be played, see `try_init`.
     pub fn init(script: &str, use_worry_modulus: bool) -> Self {
+        Self::try_init(script, use_worry_modulus).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_init(script: &str, use_worry_modulus: bool) -> Result<Self, ModulusError> {
         let monkeys: Vec<Monkey> = script.split("\n\n").map(Monkey::parse).collect();
 
         let worry_modulus = if use_worry_modulus {
-            Some(monkeys.iter().map(|m| m.decision.divisible_by).fold(1, |x, y| x*y))
+            if let Some(monkey) = monkeys.iter().position(|m| m.operation.uses_division()) {
+                return Err(ModulusError { monkey });
+            }
+            Some(monkeys.iter().map(|m| m.decision.divisible_by).product())
         } else {
             None
         };
 
-        Self {
+        Ok(Self {
             round: 0,
             worry_modulus,
             monkeys,
-        }
+        })
     }
 
     pub fn round(&mut self) {
@@ -179,6 +215,73 @@ impl MonkeyInTheMiddle {
         self.round += 1;
     }
 
+    /// Follows a single item round by round until its state at the start of
+    /// a round repeats. Items never affect each other, so each one can be
+    /// traced on its own.
+    fn trace_item(&self, monkey: usize, worry: WorryLevel) -> ItemCycle {
+        let mut seen: HashMap<(usize, WorryLevel), usize> = HashMap::new();
+        let mut counts = vec![vec![0; self.monkeys.len()]];
+        let mut state = (monkey, worry);
+
+        loop {
+            let round = counts.len() - 1;
+            if let Some(&start) = seen.get(&state) {
+                return ItemCycle { counts, start, len: round - start };
+            }
+            seen.insert(state, round);
+
+            let mut round_counts = counts[round].clone();
+            let (mut monkey, mut worry) = state;
+            // Thrown to a monkey yet to take its turn, the item is inspected
+            // again this round
+            loop {
+                round_counts[monkey] += 1;
+                worry = self.monkeys[monkey].alter(worry, self.worry_modulus);
+                let target = self.monkeys[monkey].decision.check(worry);
+                if target > monkey {
+                    monkey = target;
+                } else {
+                    state = (target, worry);
+                    break;
+                }
+            }
+            counts.push(round_counts);
+        }
+    }
+
+    /// The inspection counts after playing another `rounds` rounds, without
+    /// playing them. Each item is traced until it falls into a cycle, which
+    /// needs the worry modulus to keep the number of states bounded, so this
+    /// is `None` for a game without one.
+    pub fn inspections_after(&self, rounds: usize) -> Option<Vec<u128>> {
+        self.worry_modulus?;
+
+        let mut inspections: Vec<u128> = self.inspections().iter().map(|&n| n as u128).collect();
+        for (idx, monkey) in self.monkeys.iter().enumerate() {
+            for item in &monkey.items {
+                let item_counts = self.trace_item(idx, *item).counts_after(rounds);
+                inspections.iter_mut().zip(item_counts).for_each(|(total, n)| *total += n);
+            }
+        }
+
+        Some(inspections)
+    }
+
+    /// Monkey business after another `rounds` rounds. `None` without a worry
+    /// modulus, with fewer than two monkeys, or if the product of the two
+    /// largest counts doesn't fit in a `u128`.
+    pub fn monkey_business_after(&self, rounds: usize) -> Option<u128> {
+        let mut inspections = self.inspections_after(rounds)?;
+
+        inspections.sort();
+        inspections.reverse();
+
+        match inspections[..] {
+            [first, second, ..] => first.checked_mul(second),
+            _ => None,
+        }
+    }
+
     pub fn inspections(&self) -> Vec<usize> {
         self.monkeys.iter().map(|m| m.num_inspections).collect()
     }
@@ -207,6 +310,41 @@ mod tests {
         assert_eq!(monkey.items, vec![79, 98]);
     }
 
+    #[test]
+    fn parse_expression_operations() {
+        let monkey = Monkey::parse(
+            "Monkey 0:\n  Starting items: 4\n  Operation: new = old * 2 + old\n  Test: divisible by 3\n    If true: throw to monkey 1\n    If false: throw to monkey 2",
+        );
+
+        assert_eq!(monkey.alter(4, None), 4);
+        assert_eq!(monkey.alter(4, Some(5)), 2);
+        assert_eq!(monkey.throw_queue(None), vec![(4, 2)]);
+    }
+
+    #[test]
+    fn worry_below_zero() {
+        let script = "Monkey 0:\n  Starting items: 4\n  Operation: new = old - 10\n  Test: divisible by 2\n    If true: throw to monkey 1\n    If false: throw to monkey 0\n\n\
+                      Monkey 1:\n  Starting items: 7\n  Operation: new = old * 3\n  Test: divisible by 5\n    If true: throw to monkey 0\n    If false: throw to monkey 0";
+        let mut game = MonkeyInTheMiddle::init(script, false);
+
+        // (4 - 10) / 3 rounds towards zero
+        assert_eq!(game.monkeys[0].throw_queue(None), vec![(-2, 1)]);
+
+        game.round();
+        assert_eq!(game.monkeys[0].items, vec![7, -2]);
+    }
+
+    #[test]
+    fn division_needs_no_modulus() {
+        let input = include_str!("test-input-1.txt").trim().replace("new = old + 3", "new = (old + 3) / 2");
+
+        assert_eq!(MonkeyInTheMiddle::try_init(&input, true).err(), Some(ModulusError { monkey: 3 }));
+
+        let mut game = MonkeyInTheMiddle::try_init(&input, false).unwrap();
+        game.round();
+        assert_eq!(game.monkeys[3].num_inspections, 5);
+    }
+
     #[test]
     fn part_2() {
         let input = include_str!("test-input-1.txt").trim();
@@ -225,6 +363,51 @@ mod tests {
         assert_eq!(game.inspections(), vec![99, 97, 8, 103]);
     }
 
+    #[test]
+    fn extrapolates_cycles() {
+        let input = include_str!("test-input-1.txt").trim();
+        let mut game = MonkeyInTheMiddle::init(input, true);
+
+        assert_eq!(game.inspections_after(20), Some(vec![99, 97, 8, 103]));
+        assert_eq!(game.inspections_after(10000), Some(vec![52166, 47830, 1938, 52013]));
+        assert_eq!(game.monkey_business_after(10000), Some(2713310158));
+
+        // Extrapolating from part way through a game gives the same totals
+        // as extrapolating from the start
+        let rounds = 1_000_000_000_000;
+        let expected = game.inspections_after(rounds);
+        for _ in 0..1234 {
+            game.round();
+        }
+        assert_eq!(game.inspections_after(rounds - 1234), expected);
+        assert_eq!(game.inspections_after(1000), MonkeyInTheMiddle::init(input, true).inspections_after(2234));
+    }
+
+    #[test]
+    fn extrapolates_past_usize() {
+        let input = include_str!("test-input-1.txt").trim();
+        let game = MonkeyInTheMiddle::init(input, true);
+
+        let inspections = game.inspections_after(usize::MAX).unwrap();
+        // Monkey 2 only sees an item every few rounds
+        assert!(inspections[2] < usize::MAX as u128);
+        assert!(inspections[3] > usize::MAX as u128);
+        assert_eq!(game.monkey_business_after(usize::MAX), None);
+    }
+
+    #[test]
+    fn extrapolation_needs_modulus_and_two_monkeys() {
+        let input = include_str!("test-input-1.txt").trim();
+        let game = MonkeyInTheMiddle::init(input, false);
+        assert_eq!(game.inspections_after(20), None);
+        assert_eq!(game.monkey_business_after(20), None);
+
+        let first = input.split("\n\n").next().unwrap().replace("monkey 2", "monkey 0").replace("monkey 3", "monkey 0");
+        let game = MonkeyInTheMiddle::init(&first, true);
+        assert_eq!(game.inspections_after(20), Some(vec![40]));
+        assert_eq!(game.monkey_business_after(20), None);
+    }
+
     #[test]
     fn round() {
         let input = include_str!("test-input-1.txt").trim();
@@ -1,4 +1,5 @@
 use day_11::MonkeyInTheMiddle;
+use std::env;
 
 fn main() {
     let input = include_str!("input.txt");
@@ -10,4 +11,20 @@ fn main() {
     let mut game_pt_2 = MonkeyInTheMiddle::init(input, true);
     for _ in 0..10000 { game_pt_2.round() };
     println!("{}", game_pt_2.monkey_business());
+
+    // Usage: day-11 [--rounds <n>]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let rounds: Option<usize> = args
+        .iter()
+        .position(|a| a == "--rounds")
+        .map(|i| args.get(i + 1).expect("Expected a value after --rounds"))
+        .map(|n| n.parse().expect("Expected --rounds to be numeric"));
+
+    if let Some(rounds) = rounds {
+        let game = MonkeyInTheMiddle::init(input, true);
+        match game.monkey_business_after(rounds) {
+            Some(business) => println!("{}", business),
+            None => println!("monkey business after {} rounds doesn't fit in a u128", rounds),
+        }
+    }
 }
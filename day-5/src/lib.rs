@@ -0,0 +1,194 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref MOVE_INSTR_REGEX: Regex = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
+}
+
+pub type Stacks = Vec<Vec<char>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CraneModel {
+    /// Moves crates one at a time (part 1)
+    CrateMover9000,
+    /// Moves several crates at once, preserving their order (part 2)
+    CrateMover9001,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MoveInstruction {
+    pub num: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl MoveInstruction {
+    pub fn parse(str: &str) -> Self {
+        let captures = MOVE_INSTR_REGEX.captures(str).expect("Expected input to match regex");
+
+        Self {
+            num: captures[1].parse::<usize>().expect("Could not parse digit"),
+            from: captures[2].parse::<usize>().expect("Could not parse digit") - 1,
+            to: captures[3].parse::<usize>().expect("Could not parse digit") - 1
+        }
+    }
+
+    pub fn exec_part_1(&self, stacks: &mut Stacks) {
+        for _ in 0..self.num {
+            let from_val = stacks[self.from].pop().expect("Expected a value in from vec");
+            stacks[self.to].push(from_val);
+        }
+    }
+
+    pub fn exec_part_2(&self, stacks: &mut Stacks) {
+        let mut buffer: Vec<char> = Vec::new();
+        for _ in 0..self.num {
+            let from_val = stacks[self.from].pop().expect("Expected a value in from vec");
+            buffer.push(from_val);
+        }
+        while let Some(val) = buffer.pop() {
+            stacks[self.to].push(val);
+        }
+    }
+
+    pub fn exec(&self, stacks: &mut Stacks, model: CraneModel) {
+        match model {
+            CraneModel::CrateMover9000 => self.exec_part_1(stacks),
+            CraneModel::CrateMover9001 => self.exec_part_2(stacks),
+        }
+    }
+}
+
+/// Parses the puzzle's drawing of the starting stacks, e.g.
+///
+/// ```text
+///     [D]
+/// [N] [C]
+/// [Z] [M] [P]
+///  1   2   3
+/// ```
+pub fn parse_stacks(drawing: &str) -> Stacks {
+    let mut lines: Vec<&str> = drawing.lines().filter(|l| !l.trim().is_empty()).collect();
+    let labels = lines.pop().expect("Expected a line of stack labels");
+    let num_stacks = labels.split_whitespace().count();
+
+    let mut stacks: Stacks = vec![Vec::new(); num_stacks];
+
+    // Crates are drawn top to bottom, so push from the bottom line up
+    for line in lines.iter().rev() {
+        for (i, stack) in stacks.iter_mut().enumerate() {
+            match line.as_bytes().get(i * 4 + 1) {
+                Some(b' ') | None => (),
+                Some(c) => stack.push(*c as char),
+            }
+        }
+    }
+
+    stacks
+}
+
+/// Renders stacks in the same format as the puzzle's drawing, the inverse of
+/// `parse_stacks`.
+pub fn render_stacks(stacks: &Stacks) -> String {
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let mut lines: Vec<String> = Vec::new();
+
+    for level in (0..height).rev() {
+        let line: Vec<String> = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(c) => format!("[{}]", c),
+                None => "   ".to_string(),
+            })
+            .collect();
+        lines.push(line.join(" ").trim_end().to_string());
+    }
+
+    let labels: Vec<String> = (1..=stacks.len()).map(|n| format!(" {} ", n)).collect();
+    lines.push(labels.join(" ").trim_end().to_string());
+
+    lines.join("\n")
+}
+
+pub fn top_crates(stacks: &Stacks) -> String {
+    stacks.iter().filter_map(|stack| stack.last()).collect()
+}
+
+/// Applies instructions one at a time, yielding the instruction along with
+/// the full state of the stacks after it has been executed.
+pub struct Replay<'a> {
+    stacks: Stacks,
+    model: CraneModel,
+    instructions: std::slice::Iter<'a, MoveInstruction>,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(stacks: Stacks, instructions: &'a [MoveInstruction], model: CraneModel) -> Self {
+        Self {
+            stacks,
+            model,
+            instructions: instructions.iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = (&'a MoveInstruction, Stacks);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = self.instructions.next()?;
+        instruction.exec(&mut self.stacks, self.model);
+        Some((instruction, self.stacks.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_DRAWING: &str = "    [D]    \n\
+                                   [N] [C]    \n\
+                                   [Z] [M] [P]\n 1   2   3 ";
+
+    const EXAMPLE_MOVES: &str = "move 1 from 2 to 1\n\
+                                 move 3 from 1 to 3\n\
+                                 move 2 from 2 to 1\n\
+                                 move 1 from 1 to 2";
+
+    #[test]
+    fn parse_drawing() {
+        let stacks = parse_stacks(EXAMPLE_DRAWING);
+        assert_eq!(stacks, vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+    }
+
+    #[test]
+    fn render_round_trips() {
+        let stacks = parse_stacks(EXAMPLE_DRAWING);
+        let rendered = render_stacks(&stacks);
+
+        assert_eq!(rendered, "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3");
+        assert_eq!(parse_stacks(&rendered), stacks);
+    }
+
+    #[test]
+    fn replay_yields_every_step() {
+        let instructions: Vec<MoveInstruction> = EXAMPLE_MOVES.lines().map(MoveInstruction::parse).collect();
+        let frames: Vec<Stacks> = Replay::new(parse_stacks(EXAMPLE_DRAWING), &instructions, CraneModel::CrateMover9000)
+            .map(|(_, stacks)| stacks)
+            .collect();
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0], vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]);
+        assert_eq!(top_crates(frames.last().unwrap()), "CMZ");
+    }
+
+    #[test]
+    fn replay_part_2() {
+        let instructions: Vec<MoveInstruction> = EXAMPLE_MOVES.lines().map(MoveInstruction::parse).collect();
+        let (_, last) = Replay::new(parse_stacks(EXAMPLE_DRAWING), &instructions, CraneModel::CrateMover9001)
+            .last()
+            .unwrap();
+
+        assert_eq!(top_crates(&last), "MCD");
+    }
+}
@@ -1,75 +1,68 @@
-use regex::Regex;
-use lazy_static::lazy_static;
+use day_5::{render_stacks, top_crates, CraneModel, MoveInstruction, Replay, Stacks};
+use std::{env, thread, time::Duration};
 
-lazy_static! {
-    static ref MOVE_INSTR_REGEX: Regex = Regex::new(r"^move (\d+) from (\d+) to (\d)+$").unwrap();
-}
-
-type Stacks<'a> = [&'a mut Vec<char>; 9];
+fn main() {
+    // input.txt only has the moves, so the starting drawing is transcribed
+    // here rather than run through `parse_stacks`.
+    let stacks: Stacks = vec![
+        Vec::from(['H', 'B', 'V', 'W', 'N', 'M', 'L', 'P']),
+        Vec::from(['M', 'Q', 'H']),
+        Vec::from(['N', 'D', 'B', 'G', 'F', 'Q', 'M', 'L']),
+        Vec::from(['Z', 'T', 'F', 'Q', 'M', 'W', 'G']),
+        Vec::from(['M', 'T', 'H', 'P']),
+        Vec::from(['C', 'B', 'M', 'J', 'D', 'H', 'G', 'T']),
+        Vec::from(['M', 'N', 'B', 'F', 'V', 'R']),
+        Vec::from(['P', 'L', 'H', 'M', 'R', 'G', 'S']),
+        Vec::from(['P', 'D', 'B', 'C', 'N']),
+    ];
 
-#[derive(Debug)]
-struct MoveInstruction {
-    num: usize,
-    from: usize,
-    to: usize,
-}
+    // Usage: day-5 [--part-1] [--animate] [--fps <n>]
+    // --fps redraws in place at that rate, and implies --animate
+    let args: Vec<String> = env::args().skip(1).collect();
+    let model = if args.iter().any(|a| a == "--part-1") {
+        CraneModel::CrateMover9000
+    } else {
+        CraneModel::CrateMover9001
+    };
+    let fps: Option<f64> = args
+        .iter()
+        .position(|a| a == "--fps")
+        .map(|i| args.get(i + 1).expect("Expected a value after --fps"))
+        .map(|n| n.parse().expect("Expected --fps to be numeric"));
+    if let Some(fps) = fps {
+        assert!(fps > 0.0 && fps.is_finite(), "Expected --fps to be positive, got {}", fps);
+    }
+    let animate = fps.is_some() || args.iter().any(|a| a == "--animate");
 
-impl MoveInstruction {
-    pub fn parse(str: &str) -> Self {
-        let captures = MOVE_INSTR_REGEX.captures(str).expect("Expected input to match regex");
+    let contents = include_str!("input.txt").trim();
+    let instructions: Vec<MoveInstruction> = contents.lines().map(MoveInstruction::parse).collect();
 
-        Self {
-            num: captures[1].parse::<usize>().expect("Could not parse digit"),
-            from: captures[2].parse::<usize>().expect("Could not parse digit") - 1,
-            to: captures[3].parse::<usize>().expect("Could not parse digit") - 1
-        }
+    if animate {
+        println!("{}\n", render_stacks(&stacks));
     }
 
-    pub fn exec_part_1(self: &Self, stacks: &mut Stacks) {
-        for _ in 0..self.num {
-            let from_val = stacks[self.from].pop().expect("Expected a value in from vec");
-            stacks[self.to].push(from_val);
-        }
-    }
+    let mut last = stacks.clone();
+    for (instruction, frame) in Replay::new(stacks, &instructions, model) {
+        if animate {
+            let move_line = format!(
+                "move {} from {} to {}",
+                instruction.num,
+                instruction.from + 1,
+                instruction.to + 1
+            );
 
-    pub fn exec_part_2(self: &Self, stacks: &mut Stacks) {
-        let mut buffer: Vec<char> = Vec::new();
-        for _ in 0..self.num {
-            let from_val = stacks[self.from].pop().expect("Expected a value in from vec");
-            buffer.push(from_val);
-        }
-        while !buffer.is_empty() {
-            stacks[self.to].push(buffer.pop().unwrap());
+            match fps {
+                Some(fps) => {
+                    // Clear the terminal and redraw in place
+                    print!("\x1b[2J\x1b[H");
+                    println!("{}\n\n{}", move_line, render_stacks(&frame));
+                    thread::sleep(Duration::from_secs_f64(1.0 / fps));
+                }
+                None => println!("{}\n\n{}\n", move_line, render_stacks(&frame)),
+            }
         }
+        last = frame;
     }
-}
-
-fn main() {
-    // I could write a function to parse and generate this from the test input,
-    // but nah.
-    let mut stacks: Stacks = [
-        &mut Vec::from(['H', 'B', 'V', 'W', 'N', 'M', 'L', 'P']),
-        &mut Vec::from(['M', 'Q', 'H']),
-        &mut Vec::from(['N', 'D', 'B', 'G', 'F', 'Q', 'M', 'L']),
-        &mut Vec::from(['Z', 'T', 'F', 'Q', 'M', 'W', 'G']),
-        &mut Vec::from(['M', 'T', 'H', 'P']),
-        &mut Vec::from(['C', 'B', 'M', 'J', 'D', 'H', 'G', 'T']),
-        &mut Vec::from(['M', 'N', 'B', 'F', 'V', 'R']),
-        &mut Vec::from(['P', 'L', 'H', 'M', 'R', 'G', 'S']),
-        &mut Vec::from(['P', 'D', 'B', 'C', 'N']),
-    ];
 
-    let contents = include_str!("input.txt").trim();
-
-    contents.
-        lines().
-        map(MoveInstruction::parse).
-        for_each(|instruction| {
-            // instruction.exec_part_1(&mut stacks)
-            instruction.exec_part_2(&mut stacks)
-        });
-
-    for stack in stacks {
-        print!("{}", stack.last().unwrap())
-    }
+    println!("{}", top_crates(&last));
 }
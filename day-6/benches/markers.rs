@@ -0,0 +1,57 @@
+use day_6::index_of_first_n_distinct_chars;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// The original implementation, kept here for comparison
+fn all_elements_uniq(slice: &[u8]) -> bool {
+    let mut set: HashSet<u8> = HashSet::new();
+    for item in slice {
+        if !set.insert(*item) {
+            return false;
+        }
+    }
+    true
+}
+
+fn naive(n: usize, string: &str) -> Option<usize> {
+    string
+        .as_bytes()
+        .windows(n)
+        .position(all_elements_uniq)
+        .map(|index| index + n)
+}
+
+/// A stream of `len` bytes with no 14 distinct bytes in a row until the very
+/// end, so both implementations have to scan all of it.
+fn generate_stream(len: usize) -> String {
+    let mut stream: String = "abcdefghijklm".chars().cycle().take(len).collect();
+    stream.push_str("nopqrstuvwxyz");
+    stream
+}
+
+fn time<F: FnMut() -> Option<usize>>(iterations: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    for megabytes in [1, 4, 16] {
+        let stream = generate_stream(megabytes * 1024 * 1024);
+        assert_eq!(naive(14, &stream), index_of_first_n_distinct_chars(14, &stream));
+
+        let naive_time = time(3, || naive(14, black_box(&stream)));
+        let single_pass_time = time(3, || index_of_first_n_distinct_chars(14, black_box(&stream)));
+
+        println!(
+            "{:>3} MB: naive {:>10.2?}, single pass {:>10.2?} ({:.1}x)",
+            megabytes,
+            naive_time,
+            single_pass_time,
+            naive_time.as_secs_f64() / single_pass_time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+pub const START_OF_PACKET_LEN: usize = 4;
+pub const START_OF_MESSAGE_LEN: usize = 14;
+
+/// Tracks the longest run of distinct bytes ending at the most recently
+/// pushed byte.
+///
+/// Rather than rebuilding a set for every window, this keeps the last offset
+/// each byte was seen at. When a byte repeats inside the current window, the
+/// window start jumps past its previous occurrence.
+#[derive(Debug, Clone)]
+pub struct DistinctWindow {
+    n: usize,
+    last_seen: [Option<usize>; 256],
+    window_start: usize,
+}
+
+impl DistinctWindow {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            last_seen: [None; 256],
+            window_start: 0,
+        }
+    }
+
+    /// Feeds the byte at `offset`, returning true if it completes a window of
+    /// `n` distinct bytes. Offsets must be pushed in increasing order.
+    pub fn push(&mut self, offset: usize, byte: u8) -> bool {
+        if let Some(prev) = self.last_seen[byte as usize] {
+            if prev >= self.window_start {
+                self.window_start = prev + 1;
+            }
+        }
+        self.last_seen[byte as usize] = Some(offset);
+
+        offset + 1 - self.window_start >= self.n
+    }
+
+    /// Starts a fresh window after `offset`, so the next marker can't share
+    /// any bytes with the one just found.
+    pub fn reset(&mut self, offset: usize) {
+        self.window_start = offset + 1;
+    }
+}
+
+/// Returns the number of bytes consumed up to and including the first run of
+/// `n` distinct bytes, in a single pass over `string`.
+pub fn index_of_first_n_distinct_chars(n: usize, string: &str) -> Option<usize> {
+    index_of_first_n_distinct_bytes(n, string.as_bytes())
+}
+
+/// Like `index_of_first_n_distinct_chars`, for data that isn't text.
+pub fn index_of_first_n_distinct_bytes(n: usize, data: &[u8]) -> Option<usize> {
+    if n == 0 {
+        return Some(0);
+    }
+
+    let mut window = DistinctWindow::new(n);
+    data.iter()
+        .enumerate()
+        .position(|(i, &byte)| window.push(i, byte))
+        .map(|i| i + 1)
+}
+
+pub fn start_of_packet(string: &str) -> Option<usize> {
+    index_of_first_n_distinct_chars(START_OF_PACKET_LEN, string)
+}
+
+pub fn start_of_message(string: &str) -> Option<usize> {
+    index_of_first_n_distinct_chars(START_OF_MESSAGE_LEN, string)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl MarkerKind {
+    pub fn window_len(&self) -> usize {
+        match self {
+            MarkerKind::StartOfPacket => START_OF_PACKET_LEN,
+            MarkerKind::StartOfMessage => START_OF_MESSAGE_LEN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    /// Number of bytes consumed from the start of the stream up to and
+    /// including the end of the marker, as in the puzzle answers.
+    pub offset: usize,
+}
+
+/// Scans a datastream incrementally, yielding every start-of-packet and
+/// start-of-message marker as soon as its last byte has been read.
+///
+/// After a marker is reported its window is reset, so consecutive markers of
+/// the same kind never overlap. Markers of different kinds are tracked
+/// independently.
+pub struct MarkerDetector<R: BufRead> {
+    reader: R,
+    scanner: Scanner,
+    done: bool,
+}
+
+struct Scanner {
+    offset: usize,
+    packet: DistinctWindow,
+    message: DistinctWindow,
+    pending: VecDeque<Marker>,
+}
+
+impl Scanner {
+    fn feed(&mut self, byte: u8) {
+        let offset = self.offset;
+
+        if self.packet.push(offset, byte) {
+            self.packet.reset(offset);
+            self.pending.push_back(Marker { kind: MarkerKind::StartOfPacket, offset: offset + 1 });
+        }
+        if self.message.push(offset, byte) {
+            self.message.reset(offset);
+            self.pending.push_back(Marker { kind: MarkerKind::StartOfMessage, offset: offset + 1 });
+        }
+
+        self.offset += 1;
+    }
+}
+
+impl<R: BufRead> MarkerDetector<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            scanner: Scanner {
+                offset: 0,
+                packet: DistinctWindow::new(START_OF_PACKET_LEN),
+                message: DistinctWindow::new(START_OF_MESSAGE_LEN),
+                pending: VecDeque::new(),
+            },
+            done: false,
+        }
+    }
+
+    /// Total number of bytes read from the stream so far.
+    pub fn bytes_read(&self) -> usize {
+        self.scanner.offset
+    }
+}
+
+impl<R: BufRead> Iterator for MarkerDetector<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.scanner.pending.is_empty() && !self.done {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+
+            if buf.is_empty() {
+                self.done = true;
+                break;
+            }
+
+            // Stop consuming as soon as a marker is found, so callers see
+            // markers while the rest of the stream is still unread
+            let mut consumed = 0;
+            for &byte in buf {
+                self.scanner.feed(byte);
+                consumed += 1;
+                if !self.scanner.pending.is_empty() {
+                    break;
+                }
+            }
+            self.reader.consume(consumed);
+        }
+
+        self.scanner.pending.pop_front().map(Ok)
+    }
+}
+
+/// A message framed by start-of-message markers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Offset of the first byte of the start-of-message marker.
+    pub marker_offset: usize,
+    /// Offset of the first byte after the marker, i.e. the puzzle answer for
+    /// the first frame.
+    pub payload_offset: usize,
+    /// Everything up to the next marker, or the end of the stream.
+    pub payload: &'a [u8],
+}
+
+/// Splits a datastream into frames: each start-of-message marker begins a
+/// frame, whose payload runs until the next marker begins.
+///
+/// Markers never overlap, so a payload can't itself contain a run of 14
+/// distinct bytes. Anything before the first marker is preamble and is not
+/// part of any frame.
+///
+/// A receiver only trusts the stream once it has seen a start-of-packet
+/// marker, see `packet_offset`. Framing doesn't have to wait for it: any run
+/// of 14 distinct bytes contains a run of 4, so the first start-of-packet
+/// marker always ends no later than the first start-of-message marker, and
+/// every frame starts after synchronisation.
+pub struct FrameDecoder<'a> {
+    data: &'a [u8],
+    window: DistinctWindow,
+    scanned: usize,
+    next_marker_end: Option<usize>,
+}
+
+impl<'a> FrameDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut decoder = Self {
+            data,
+            window: DistinctWindow::new(START_OF_MESSAGE_LEN),
+            scanned: 0,
+            next_marker_end: None,
+        };
+        decoder.next_marker_end = decoder.find_marker_end();
+        decoder
+    }
+
+    /// Offset just past the first start-of-packet marker, where a receiver
+    /// would consider itself synchronised with the stream.
+    pub fn packet_offset(&self) -> Option<usize> {
+        index_of_first_n_distinct_bytes(START_OF_PACKET_LEN, self.data)
+    }
+
+    fn find_marker_end(&mut self) -> Option<usize> {
+        while self.scanned < self.data.len() {
+            let offset = self.scanned;
+            self.scanned += 1;
+
+            if self.window.push(offset, self.data[offset]) {
+                self.window.reset(offset);
+                return Some(offset + 1);
+            }
+        }
+        None
+    }
+}
+
+impl<'a> Iterator for FrameDecoder<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let payload_offset = self.next_marker_end?;
+        self.next_marker_end = self.find_marker_end();
+
+        let payload_end = match self.next_marker_end {
+            Some(end) => end - START_OF_MESSAGE_LEN,
+            None => self.data.len(),
+        };
+
+        Some(Frame {
+            marker_offset: payload_offset - START_OF_MESSAGE_LEN,
+            payload_offset,
+            payload: &self.data[payload_offset..payload_end],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn examples() {
+        for (stream, packet, message) in EXAMPLES {
+            assert_eq!(start_of_packet(stream), Some(packet), "{}", stream);
+            assert_eq!(start_of_message(stream), Some(message), "{}", stream);
+        }
+    }
+
+    #[test]
+    fn no_marker() {
+        assert_eq!(index_of_first_n_distinct_chars(4, "abcabcabc"), None);
+        assert_eq!(index_of_first_n_distinct_chars(4, "abc"), None);
+        assert_eq!(index_of_first_n_distinct_chars(3, "abc"), Some(3));
+    }
+
+    #[test]
+    fn detector_finds_first_markers() {
+        for (stream, packet, message) in EXAMPLES {
+            let markers: Vec<Marker> = MarkerDetector::new(stream.as_bytes())
+                .map(Result::unwrap)
+                .collect();
+
+            let first_packet = markers.iter().find(|m| m.kind == MarkerKind::StartOfPacket);
+            let first_message = markers.iter().find(|m| m.kind == MarkerKind::StartOfMessage);
+
+            assert_eq!(first_packet.map(|m| m.offset), Some(packet), "{}", stream);
+            assert_eq!(first_message.map(|m| m.offset), Some(message), "{}", stream);
+        }
+    }
+
+    #[test]
+    fn detector_finds_every_marker() {
+        // Tiny buffer to check markers spanning reads are still found
+        let stream = "aabcdeeffghijkk";
+        let reader = io::BufReader::with_capacity(3, stream.as_bytes());
+        let offsets: Vec<usize> = MarkerDetector::new(reader)
+            .map(Result::unwrap)
+            .filter(|m| m.kind == MarkerKind::StartOfPacket)
+            .map(|m| m.offset)
+            .collect();
+
+        assert_eq!(offsets, vec![5, 12]);
+    }
+
+    #[test]
+    fn frames_from_examples() {
+        for (stream, packet, message) in EXAMPLES {
+            let decoder = FrameDecoder::new(stream.as_bytes());
+            assert_eq!(decoder.packet_offset(), Some(packet));
+
+            let frames: Vec<Frame> = decoder.collect();
+            assert_eq!(frames.len(), 1, "{}", stream);
+            assert!(packet <= frames[0].payload_offset);
+            assert_eq!(frames[0].payload_offset, message);
+            assert_eq!(frames[0].payload, &stream.as_bytes()[message..]);
+        }
+    }
+
+    #[test]
+    fn frames_split_on_markers() {
+        let stream = "aaaaabcdefghijklmnhelloopqrstuvwxyzabworld";
+        let frames: Vec<Frame> = FrameDecoder::new(stream.as_bytes()).collect();
+
+        assert_eq!(
+            frames,
+            vec![
+                Frame { marker_offset: 4, payload_offset: 18, payload: b"hello" },
+                Frame { marker_offset: 23, payload_offset: 37, payload: b"world" },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_frames_without_marker() {
+        assert_eq!(FrameDecoder::new(b"abcabcabc").count(), 0);
+    }
+}
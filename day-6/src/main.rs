@@ -1,32 +1,28 @@
-use std::collections::HashSet;
-use std::hash::Hash;
+use day_6::{start_of_message, start_of_packet, MarkerDetector};
+use std::{env, fs::File, io};
 
-fn all_elements_uniq<T: Hash + Eq + Copy>(slice: &[T]) -> bool {
-    let mut set: HashSet<T> = HashSet::new();
-    for item in slice {
-        if set.contains(item) {
-            return false;
+fn main() {
+    // Usage: day-6 [<path> | -]
+    // With an argument, streams the given file (or stdin) and prints every
+    // marker found rather than solving the puzzle input.
+    if let Some(path) = env::args().nth(1) {
+        let reader: Box<dyn io::BufRead> = if path == "-" {
+            Box::new(io::stdin().lock())
         } else {
-            set.insert(*item);
+            Box::new(io::BufReader::new(File::open(&path).expect("could not open input")))
+        };
+
+        for marker in MarkerDetector::new(reader) {
+            let marker = marker.expect("could not read input");
+            println!("{:?} {}", marker.kind, marker.offset);
         }
+        return;
     }
-    true
-}
 
-fn index_of_first_n_distinct_chars(n: usize, string: &str) -> Option<usize> {
-    string
-        .as_bytes()
-        .windows(n)
-        .enumerate()
-        .find(|(_, window)| all_elements_uniq(window))
-        .map(|(index, _)| index + n)
-}
-
-fn main() {
     let contents = include_str!("input.txt").trim();
 
-    let part_1 = index_of_first_n_distinct_chars(4, contents).expect("no valid answer for part 1");
-    let part_2 = index_of_first_n_distinct_chars(14, contents).expect("no valid answer for part 2");
+    let part_1 = start_of_packet(contents).expect("no valid answer for part 1");
+    let part_2 = start_of_message(contents).expect("no valid answer for part 2");
 
     println!("Part 1: {}. Part 2: {}", part_1, part_2);
 }
@@ -0,0 +1,110 @@
+use day_7::{human_size, render_du_from, ExecutedCommand, FsTree, REQUIRED_FREE_SPACE};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+};
+
+// Usage: shell [<transcript>]
+// Loads a transcript (the puzzle input by default) and lets you poke around
+// the reconstructed filesystem.
+fn main() {
+    let contents = match env::args().nth(1) {
+        Some(path) => fs::read_to_string(path).expect("could not read transcript"),
+        None => include_str!("../input.txt").to_string(),
+    };
+
+    let commands = ExecutedCommand::parse_transcript(&contents).unwrap_or_else(|e| panic!("{}", e));
+    let replay = FsTree::replay(&commands);
+    for issue in &replay.issues {
+        eprintln!("warning: {}", issue);
+    }
+
+    let mut tree = replay.tree;
+    let mut pwd = tree.root();
+    print_space(&tree);
+
+    let stdin = io::stdin();
+    loop {
+        print!("{} $ ", tree.path(pwd));
+        io::stdout().flush().expect("could not flush stdout");
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("could not read stdin") == 0 {
+            break;
+        }
+
+        let mut parts = line.split_whitespace();
+        let cmd = parts.next();
+        let arg = parts.next();
+
+        match (cmd, arg) {
+            (None, _) => continue,
+            (Some("exit" | "quit"), _) => break,
+            (Some("pwd"), _) => println!("{}", tree.path(pwd)),
+            (Some("cd"), arg) => match tree.resolve(pwd, arg.unwrap_or("/")) {
+                Some(idx) if tree.get(idx).is_dir => pwd = idx,
+                Some(_) => println!("cd: not a directory"),
+                None => println!("cd: no such directory"),
+            },
+            (Some("ls"), arg) => match tree.resolve(pwd, arg.unwrap_or(".")) {
+                Some(idx) => {
+                    for node in tree.list(idx) {
+                        if node.is_dir {
+                            println!("dir {}", node.name);
+                        } else {
+                            println!("{} {}", node.size, node.name);
+                        }
+                    }
+                }
+                None => println!("ls: no such file or directory"),
+            },
+            (Some("du"), arg) => match tree.resolve(pwd, arg.unwrap_or(".")) {
+                Some(idx) => println!("{}", render_du_from(&tree, idx)),
+                None => println!("du: no such file or directory"),
+            },
+            (Some("find"), Some(pattern)) => {
+                for idx in tree.find(pattern) {
+                    println!("{}\t{}", tree.total_size(idx), tree.path(idx));
+                }
+            }
+            (Some("rm"), Some(path)) => match tree.resolve(pwd, path) {
+                Some(idx) if idx == tree.root() => println!("rm: refusing to remove /"),
+                Some(idx) => {
+                    // Don't leave the shell inside a removed directory
+                    if is_within(&tree, pwd, idx) {
+                        pwd = tree.get(idx).parent.unwrap_or(tree.root());
+                    }
+                    let freed = tree.remove(idx).expect("expected a non-root node");
+                    println!("freed {} ({})", freed, human_size(freed));
+                    print_space(&tree);
+                }
+                None => println!("rm: no such file or directory"),
+            },
+            (Some("df"), _) => print_space(&tree),
+            (Some("help"), _) => {
+                println!("commands: cd [path], ls [path], du [path], find <glob>, rm <path>, df, pwd, exit")
+            }
+            (Some(cmd), _) => println!("{}: unknown command or missing argument, try help", cmd),
+        }
+    }
+}
+
+fn is_within(tree: &FsTree, idx: usize, ancestor: usize) -> bool {
+    let mut curr = Some(idx);
+    while let Some(idx) = curr {
+        if idx == ancestor {
+            return true;
+        }
+        curr = tree.get(idx).parent;
+    }
+    false
+}
+
+fn print_space(tree: &FsTree) {
+    let needed = tree.space_needed();
+    if needed == 0 {
+        println!("{} free, enough for the {} update", tree.free_space(), REQUIRED_FREE_SPACE);
+    } else {
+        println!("{} free, {} more needed for the update", tree.free_space(), needed);
+    }
+}
@@ -0,0 +1,656 @@
+mod parse;
+mod render;
+
+pub use parse::{Command, ExecutedCommand, LsEntry, ParseError, ParseErrorKind};
+pub use render::{human_size, render_du, render_du_from, render_tree, to_json};
+
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+#[derive(Debug)]
+pub struct FsNode {
+    pub name: String,
+    /// Size of a file; always 0 for directories, see `FsTree::total_size`
+    pub size: usize,
+    pub is_dir: bool,
+    pub idx: usize,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    cached_size: Cell<Option<usize>>,
+}
+
+/// An arena of filesystem nodes, referring to each other by index. The root
+/// directory is always at index 0.
+#[derive(Debug)]
+pub struct FsTree {
+    data: Vec<FsNode>,
+}
+
+pub const TOTAL_DISK_SPACE: usize = 70000000;
+pub const REQUIRED_FREE_SPACE: usize = 30000000;
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FsTree {
+    pub fn new() -> Self {
+        let mut tree = Self { data: vec![] };
+        tree.insert("/".to_string(), 0, true, None);
+        tree
+    }
+
+    pub fn root(&self) -> usize {
+        0
+    }
+
+    fn insert(&mut self, name: String, size: usize, is_dir: bool, parent: Option<usize>) -> usize {
+        let idx = self.data.len();
+        let node = FsNode {
+            idx,
+            name,
+            size,
+            is_dir,
+            parent,
+            children: vec![],
+            cached_size: Cell::new(None),
+        };
+
+        self.data.push(node);
+        if let Some(parent) = parent {
+            self.data[parent].children.push(idx);
+            self.invalidate_sizes(parent);
+        }
+        idx
+    }
+
+    /// Clears the memoised size of `idx` and all of its ancestors.
+    fn invalidate_sizes(&self, idx: usize) {
+        let mut curr = Some(idx);
+        while let Some(idx) = curr {
+            self.data[idx].cached_size.set(None);
+            curr = self.data[idx].parent;
+        }
+    }
+
+    pub fn get(&self, idx: usize) -> &FsNode {
+        &self.data[idx]
+    }
+
+    /// Every node still reachable from the root.
+    pub fn nodes(&self) -> impl Iterator<Item = &FsNode> {
+        self.walk_dfs(self.root()).map(|(_, node)| node)
+    }
+
+    pub fn child(&self, dir: usize, name: &str) -> Option<usize> {
+        self.data[dir]
+            .children
+            .iter()
+            .copied()
+            .find(|&idx| self.data[idx].name == name)
+    }
+
+    /// Returns the existing entry called `name` in `dir`, creating it if it
+    /// hasn't been seen yet, so listing a directory twice is harmless.
+    fn entry(&mut self, dir: usize, name: &str, size: usize, is_dir: bool) -> usize {
+        match self.child(dir, name) {
+            Some(idx) => idx,
+            None => self.insert(name.to_string(), size, is_dir, Some(dir)),
+        }
+    }
+
+    /// Size of a file, or the recursive size of everything in a directory.
+    /// Directory sizes are memoised until something beneath them changes.
+    pub fn total_size(&self, idx: usize) -> usize {
+        let node = &self.data[idx];
+        if let Some(size) = node.cached_size.get() {
+            return size;
+        }
+
+        let size = node.size + node.children.iter().map(|&c| self.total_size(c)).sum::<usize>();
+        node.cached_size.set(Some(size));
+        size
+    }
+
+    /// Full absolute path of a node, e.g. `/a/e`.
+    pub fn path(&self, idx: usize) -> String {
+        let mut names: Vec<&str> = vec![];
+        let mut curr = idx;
+        while let Some(parent) = self.data[curr].parent {
+            names.push(&self.data[curr].name);
+            curr = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// Resolves an absolute path, or a path relative to `cwd`. Supports `.`
+    /// and `..` components; `..` at the root stays at the root.
+    pub fn resolve(&self, cwd: usize, path: &str) -> Option<usize> {
+        let mut curr = if path.starts_with('/') { self.root() } else { cwd };
+
+        for component in path.split('/') {
+            curr = match component {
+                "" | "." => curr,
+                ".." => self.data[curr].parent.unwrap_or(curr),
+                name => self.child(curr, name)?,
+            };
+        }
+
+        Some(curr)
+    }
+
+    /// Entries directly inside `dir`, in the order they were listed.
+    pub fn list(&self, dir: usize) -> impl Iterator<Item = &FsNode> {
+        self.data[dir].children.iter().map(|&idx| &self.data[idx])
+    }
+
+    /// Walks everything beneath and including `from`, depth first, yielding
+    /// each node alongside its full path.
+    pub fn walk_dfs(&self, from: usize) -> Walk<'_> {
+        Walk::new(self, from, WalkOrder::DepthFirst)
+    }
+
+    /// Like `walk_dfs`, but visits shallower nodes first.
+    pub fn walk_bfs(&self, from: usize) -> Walk<'_> {
+        Walk::new(self, from, WalkOrder::BreadthFirst)
+    }
+
+    /// Every node whose path matches `pattern`. Patterns containing a `/`
+    /// are matched against the full path, otherwise against the name only.
+    /// See `glob_match` for the supported syntax.
+    pub fn find(&self, pattern: &str) -> Vec<usize> {
+        self.walk_dfs(self.root())
+            .filter(|(path, node)| {
+                if pattern.contains('/') {
+                    glob_match(pattern, path)
+                } else {
+                    glob_match(pattern, &node.name)
+                }
+            })
+            .map(|(_, node)| node.idx)
+            .collect()
+    }
+
+    /// Parses and replays a transcript, panicking on malformed input and
+    /// silently tolerating inconsistencies. See `replay` to inspect them.
+    pub fn from_transcript(contents: &str) -> Self {
+        let commands = ExecutedCommand::parse_transcript(contents).expect("expected a valid transcript");
+        Self::replay(&commands).tree
+    }
+
+    /// Rebuilds the filesystem from parsed commands, recording anything that
+    /// doesn't add up rather than giving up on the transcript.
+    pub fn replay(commands: &[ExecutedCommand]) -> Replay {
+        let mut tree = Self::new();
+        let mut issues: Vec<Inconsistency> = vec![];
+        let mut listings: HashMap<usize, Vec<LsEntry>> = HashMap::new();
+        let mut pwd = tree.root();
+
+        for cmd in commands {
+            let mut report = |kind| issues.push(Inconsistency { line: cmd.line, kind });
+
+            match &cmd.input {
+                Command::Cd(path) => pwd = tree.replay_cd(pwd, path, &mut report),
+                Command::Ls => {
+                    // Compare sorted, so the same entries in another order
+                    // still count as the same listing
+                    let mut listing = cmd.output.clone();
+                    listing.sort();
+
+                    if let Some(previous) = listings.get(&pwd) {
+                        if *previous != listing {
+                            report(InconsistencyKind::ListedDifferently(tree.path(pwd)));
+                        }
+                    }
+
+                    for entry in &cmd.output {
+                        let idx = match entry {
+                            LsEntry::Dir(name) => tree.entry(pwd, name, 0, true),
+                            LsEntry::File { name, size } => tree.entry(pwd, name, *size, false),
+                        };
+
+                        let node = &tree.data[idx];
+                        let conflicts = match entry {
+                            LsEntry::Dir(_) => !node.is_dir,
+                            LsEntry::File { size, .. } => node.is_dir || node.size != *size,
+                        };
+                        if conflicts {
+                            report(InconsistencyKind::EntryChanged(tree.path(idx)));
+                        }
+                    }
+
+                    listings.insert(pwd, listing);
+                }
+            }
+        }
+
+        Replay { tree, issues }
+    }
+
+    /// Follows `cd` one component at a time, the same way as `resolve`, but
+    /// creating directories that were never listed. A path running into a
+    /// file leaves the working directory where it was.
+    fn replay_cd(&mut self, pwd: usize, path: &str, report: &mut impl FnMut(InconsistencyKind)) -> usize {
+        let mut dir = if path.starts_with('/') { self.root() } else { pwd };
+
+        for component in path.split('/') {
+            dir = match component {
+                "" | "." => dir,
+                ".." => match self.data[dir].parent {
+                    Some(parent) => parent,
+                    None => {
+                        report(InconsistencyKind::CdAboveRoot);
+                        dir
+                    }
+                },
+                name => match self.child(dir, name) {
+                    Some(idx) if self.data[idx].is_dir => idx,
+                    Some(_) => {
+                        report(InconsistencyKind::CdIntoFile(self.path(dir), name.to_string()));
+                        return pwd;
+                    }
+                    None => {
+                        report(InconsistencyKind::CdIntoUnlisted(self.path(dir), name.to_string()));
+                        self.entry(dir, name, 0, true)
+                    }
+                },
+            };
+        }
+
+        dir
+    }
+
+    /// Total sizes of every directory, including the root.
+    pub fn dir_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes()
+            .filter(|node| node.is_dir)
+            .map(|node| self.total_size(node.idx))
+    }
+
+    /// Detaches `idx` and everything beneath it from the tree, returning the
+    /// space freed. The root can't be removed.
+    ///
+    /// Removed nodes stay in the arena so indices remain stable, but are no
+    /// longer reachable from the root.
+    pub fn remove(&mut self, idx: usize) -> Option<usize> {
+        let parent = self.data[idx].parent?;
+        let freed = self.total_size(idx);
+
+        self.data[parent].children.retain(|&c| c != idx);
+        self.data[idx].parent = None;
+        self.invalidate_sizes(parent);
+
+        Some(freed)
+    }
+
+    pub fn free_space(&self) -> usize {
+        TOTAL_DISK_SPACE.saturating_sub(self.total_size(self.root()))
+    }
+
+    /// How much more space must be freed to run the update.
+    pub fn space_needed(&self) -> usize {
+        REQUIRED_FREE_SPACE.saturating_sub(self.free_space())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InconsistencyKind {
+    /// `cd ..` while already at the root
+    CdAboveRoot,
+    /// `cd` into a name that no `ls` of the directory has mentioned
+    CdIntoUnlisted(String, String),
+    /// `cd` into something that was listed as a file
+    CdIntoFile(String, String),
+    /// A directory was listed more than once with different contents
+    ListedDifferently(String),
+    /// An entry was listed again with a different type or size
+    EntryChanged(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    pub line: usize,
+    pub kind: InconsistencyKind,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            InconsistencyKind::CdAboveRoot => write!(f, "cd .. above /"),
+            InconsistencyKind::CdIntoUnlisted(dir, name) => write!(f, "cd into {} which was never listed in {}", name, dir),
+            InconsistencyKind::CdIntoFile(dir, name) => write!(f, "cd into {} which is a file in {}", name, dir),
+            InconsistencyKind::ListedDifferently(dir) => write!(f, "{} listed again with different contents", dir),
+            InconsistencyKind::EntryChanged(path) => write!(f, "{} listed again with a different type or size", path),
+        }
+    }
+}
+
+pub struct Replay {
+    pub tree: FsTree,
+    pub issues: Vec<Inconsistency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+    DepthFirst,
+    BreadthFirst,
+}
+
+pub struct Walk<'a> {
+    tree: &'a FsTree,
+    order: WalkOrder,
+    pending: VecDeque<(usize, String)>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(tree: &'a FsTree, from: usize, order: WalkOrder) -> Self {
+        Self {
+            tree,
+            order,
+            pending: VecDeque::from([(from, tree.path(from))]),
+        }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, &'a FsNode);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, path) = match self.order {
+            WalkOrder::DepthFirst => self.pending.pop_back()?,
+            WalkOrder::BreadthFirst => self.pending.pop_front()?,
+        };
+        let node = self.tree.get(idx);
+
+        let children = node.children.iter().map(|&c| {
+            let name = &self.tree.get(c).name;
+            let child_path = if path == "/" { format!("/{}", name) } else { format!("{}/{}", path, name) };
+            (c, child_path)
+        });
+
+        // Push in reverse for depth first so children still pop in order
+        match self.order {
+            WalkOrder::DepthFirst => self.pending.extend(children.rev()),
+            WalkOrder::BreadthFirst => self.pending.extend(children),
+        }
+
+        Some((path, node))
+    }
+}
+
+/// Matches `text` against a glob `pattern`, where `?` matches any single
+/// character except `/`, `*` matches any run of characters except `/`, and
+/// `**` matches anything, including `/`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    fn matches(p: &[char], t: &[char]) -> bool {
+        match p {
+            [] => t.is_empty(),
+            ['*', '*', rest @ ..] => (0..=t.len()).any(|i| matches(rest, &t[i..])),
+            ['*', rest @ ..] => {
+                let limit = t.iter().position(|&c| c == '/').unwrap_or(t.len());
+                (0..=limit).any(|i| matches(rest, &t[i..]))
+            }
+            ['?', rest @ ..] => matches!(t.first(), Some(&c) if c != '/') && matches(rest, &t[1..]),
+            [c, rest @ ..] => t.first() == Some(c) && matches(rest, &t[1..]),
+        }
+    }
+
+    matches(&pattern, &text)
+}
+
+pub fn sum_of_small_dirs(tree: &FsTree, max_size: usize) -> usize {
+    tree.dir_sizes().filter(|&size| size <= max_size).sum()
+}
+
+pub fn smallest_dir_to_delete(tree: &FsTree) -> Option<usize> {
+    let needed = tree.space_needed();
+
+    tree.dir_sizes().filter(|&size| size >= needed).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "$ cd /\n\
+                               $ ls\n\
+                               dir a\n\
+                               14848514 b.txt\n\
+                               8504156 c.dat\n\
+                               dir d\n\
+                               $ cd a\n\
+                               $ ls\n\
+                               dir e\n\
+                               29116 f\n\
+                               2557 g\n\
+                               62596 h.lst\n\
+                               $ cd e\n\
+                               $ ls\n\
+                               584 i\n\
+                               $ cd ..\n\
+                               $ cd ..\n\
+                               $ cd d\n\
+                               $ ls\n\
+                               4060174 j\n\
+                               8033020 d.log\n\
+                               5626152 d.ext\n\
+                               7214296 k";
+
+    #[test]
+    fn replays_transcript() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let a = tree.child(tree.root(), "a").unwrap();
+        let e = tree.child(a, "e").unwrap();
+
+        assert_eq!(tree.total_size(e), 584);
+        assert_eq!(tree.total_size(a), 94853);
+        assert_eq!(tree.total_size(tree.root()), 48381165);
+    }
+
+    #[test]
+    fn part_1() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        assert_eq!(sum_of_small_dirs(&tree, 100000), 95437);
+    }
+
+    #[test]
+    fn part_2() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        assert_eq!(smallest_dir_to_delete(&tree), Some(24933642));
+    }
+
+    #[test]
+    fn resolves_paths() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let root = tree.root();
+        let e = tree.resolve(root, "/a/e").unwrap();
+
+        assert_eq!(tree.get(e).name, "e");
+        assert_eq!(tree.path(e), "/a/e");
+        assert_eq!(tree.resolve(e, "../../d/k"), tree.resolve(root, "d/k"));
+        assert_eq!(tree.resolve(e, "./i"), tree.resolve(root, "/a/e/i"));
+        assert_eq!(tree.resolve(root, ".."), Some(root));
+        assert_eq!(tree.resolve(root, "/nope"), None);
+    }
+
+    #[test]
+    fn lists_dirs() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let names: Vec<&str> = tree.list(tree.root()).map(|n| n.name.as_str()).collect();
+
+        assert_eq!(names, vec!["a", "b.txt", "c.dat", "d"]);
+    }
+
+    #[test]
+    fn walks() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let a = tree.resolve(tree.root(), "/a").unwrap();
+
+        let dfs: Vec<String> = tree.walk_dfs(a).map(|(path, _)| path).collect();
+        assert_eq!(dfs, vec!["/a", "/a/e", "/a/e/i", "/a/f", "/a/g", "/a/h.lst"]);
+
+        let bfs: Vec<String> = tree.walk_bfs(a).map(|(path, _)| path).collect();
+        assert_eq!(bfs, vec!["/a", "/a/e", "/a/f", "/a/g", "/a/h.lst", "/a/e/i"]);
+    }
+
+    #[test]
+    fn finds_by_glob() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let paths = |pattern| -> Vec<String> { tree.find(pattern).into_iter().map(|idx| tree.path(idx)).collect() };
+
+        assert_eq!(paths("*.*"), vec!["/a/h.lst", "/b.txt", "/c.dat", "/d/d.log", "/d/d.ext"]);
+        assert_eq!(paths("d.???"), vec!["/d/d.log", "/d/d.ext"]);
+        assert_eq!(paths("/*/?"), vec!["/a/e", "/a/f", "/a/g", "/d/j", "/d/k"]);
+        assert_eq!(paths("/a/**"), vec!["/a/e", "/a/e/i", "/a/f", "/a/g", "/a/h.lst"]);
+    }
+
+    #[test]
+    fn largest_file_under_dir() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let a = tree.resolve(tree.root(), "/a").unwrap();
+        let (path, _) = tree
+            .walk_dfs(a)
+            .filter(|(_, node)| !node.is_dir)
+            .max_by_key(|(_, node)| node.size)
+            .unwrap();
+
+        assert_eq!(path, "/a/h.lst");
+    }
+
+    #[test]
+    fn sizes_are_invalidated() {
+        let mut tree = FsTree::from_transcript(EXAMPLE);
+        let e = tree.resolve(tree.root(), "/a/e").unwrap();
+        assert_eq!(tree.total_size(tree.root()), 48381165);
+
+        tree.insert("z".to_string(), 1, false, Some(e));
+        assert_eq!(tree.total_size(e), 585);
+        assert_eq!(tree.total_size(tree.root()), 48381166);
+    }
+
+    #[test]
+    fn renders_tree() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let expected = "- / (dir)\n\
+                        \x20 - a (dir)\n\
+                        \x20   - e (dir)\n\
+                        \x20     - i (file, size=584)\n\
+                        \x20   - f (file, size=29116)\n\
+                        \x20   - g (file, size=2557)\n\
+                        \x20   - h.lst (file, size=62596)\n\
+                        \x20 - b.txt (file, size=14848514)\n\
+                        \x20 - c.dat (file, size=8504156)\n\
+                        \x20 - d (dir)\n\
+                        \x20   - j (file, size=4060174)\n\
+                        \x20   - d.log (file, size=8033020)\n\
+                        \x20   - d.ext (file, size=5626152)\n\
+                        \x20   - k (file, size=7214296)";
+
+        assert_eq!(render_tree(&tree), expected);
+    }
+
+    #[test]
+    fn renders_du() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+
+        assert_eq!(render_du(&tree), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1025), "1.1K");
+    }
+
+    #[test]
+    fn renders_json() {
+        let tree = FsTree::from_transcript(EXAMPLE);
+        let json = to_json(&tree);
+
+        assert_eq!(json["size"], 48381165);
+        assert_eq!(json["children"][0]["name"], "a");
+        assert_eq!(json["children"][0]["children"][0]["children"][0]["size"], 584);
+        assert_eq!(json["children"][1]["type"], "file");
+    }
+
+    #[test]
+    fn replay_reports_inconsistencies() {
+        let transcript = "$ cd /\n\
+                          $ ls\n\
+                          dir a\n\
+                          10 b\n\
+                          $ cd ..\n\
+                          $ cd c\n\
+                          $ cd /\n\
+                          $ cd b\n\
+                          $ ls\n\
+                          dir a\n\
+                          20 b";
+        let commands = ExecutedCommand::parse_transcript(transcript).unwrap();
+        let replay = FsTree::replay(&commands);
+
+        assert_eq!(
+            replay.issues,
+            vec![
+                Inconsistency { line: 5, kind: InconsistencyKind::CdAboveRoot },
+                Inconsistency { line: 6, kind: InconsistencyKind::CdIntoUnlisted("/".to_string(), "c".to_string()) },
+                Inconsistency { line: 8, kind: InconsistencyKind::CdIntoFile("/".to_string(), "b".to_string()) },
+                Inconsistency { line: 9, kind: InconsistencyKind::ListedDifferently("/".to_string()) },
+                Inconsistency { line: 9, kind: InconsistencyKind::EntryChanged("/b".to_string()) },
+            ]
+        );
+        assert_eq!(replay.issues[0].to_string(), "line 5: cd .. above /");
+    }
+
+    #[test]
+    fn replay_follows_paths() {
+        let transcript = "$ cd /\n\
+                          $ ls\n\
+                          dir a\n\
+                          $ cd a\n\
+                          $ ls\n\
+                          dir b\n\
+                          $ cd /a/b\n\
+                          $ ls\n\
+                          10 f\n\
+                          $ cd .\n\
+                          $ cd ../b/.\n\
+                          $ ls\n\
+                          10 f\n\
+                          $ cd /a/b/f/g";
+        let commands = ExecutedCommand::parse_transcript(transcript).unwrap();
+        let replay = FsTree::replay(&commands);
+        let tree = &replay.tree;
+
+        assert_eq!(
+            replay.issues,
+            vec![Inconsistency { line: 14, kind: InconsistencyKind::CdIntoFile("/a/b".to_string(), "f".to_string()) }]
+        );
+        let paths: Vec<String> = tree.walk_dfs(tree.root()).map(|(path, _)| path).collect();
+        assert_eq!(paths, vec!["/", "/a", "/a/b", "/a/b/f"]);
+    }
+
+    #[test]
+    fn replay_of_example_is_consistent() {
+        let commands = ExecutedCommand::parse_transcript(EXAMPLE).unwrap();
+        assert!(FsTree::replay(&commands).issues.is_empty());
+    }
+
+    #[test]
+    fn remove_updates_sizes() {
+        let mut tree = FsTree::from_transcript(EXAMPLE);
+        let d = tree.resolve(tree.root(), "/d").unwrap();
+        assert_eq!(tree.space_needed(), 8381165);
+
+        assert_eq!(tree.remove(d), Some(24933642));
+        assert_eq!(tree.total_size(tree.root()), 23447523);
+        assert_eq!(tree.space_needed(), 0);
+        assert_eq!(tree.resolve(tree.root(), "/d"), None);
+        assert_eq!(sum_of_small_dirs(&tree, 100000), 95437);
+        assert_eq!(tree.remove(tree.root()), None);
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command { Cd(String), Ls }
+
+/// A single line of `ls` output.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LsEntry {
+    Dir(String),
+    File { name: String, size: usize },
+}
+
+impl LsEntry {
+    pub fn name(&self) -> &str {
+        match self {
+            LsEntry::Dir(name) => name,
+            LsEntry::File { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedCommand {
+    /// 1-based line number of the `$` line in the transcript
+    pub line: usize,
+    pub input: Command,
+    pub output: Vec<LsEntry>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnknownCommand(String),
+    /// `cd` needs exactly one argument and `ls` takes none
+    WrongArguments(String),
+    /// Output that doesn't follow an `ls`
+    UnexpectedOutput,
+    InvalidEntry(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::UnknownCommand(cmd) => write!(f, "unknown command `{}`", cmd),
+            ParseErrorKind::WrongArguments(cmd) => write!(f, "wrong number of arguments to `{}`", cmd),
+            ParseErrorKind::UnexpectedOutput => write!(f, "output without a preceding `ls`"),
+            ParseErrorKind::InvalidEntry(entry) => write!(f, "invalid ls entry `{}`", entry),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ExecutedCommand {
+    fn parse_command(line: usize, input: &str) -> Result<Command, ParseError> {
+        let error = |kind| ParseError { line, kind };
+        let mut parts = input.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let args: Vec<&str> = parts.collect();
+
+        match (name, args.as_slice()) {
+            ("cd", [path]) => Ok(Command::Cd(path.to_string())),
+            ("ls", []) => Ok(Command::Ls),
+            ("cd" | "ls", _) => Err(error(ParseErrorKind::WrongArguments(name.to_string()))),
+            _ => Err(error(ParseErrorKind::UnknownCommand(name.to_string()))),
+        }
+    }
+
+    fn parse_entry(line: usize, input: &str) -> Result<LsEntry, ParseError> {
+        let invalid = || ParseError { line, kind: ParseErrorKind::InvalidEntry(input.to_string()) };
+        // Names are everything after the first space, so may contain spaces
+        // or `$` themselves
+        let (size_or_dir, name) = input.split_once(' ').ok_or_else(invalid)?;
+        if name.is_empty() {
+            return Err(invalid());
+        }
+
+        if size_or_dir == "dir" {
+            Ok(LsEntry::Dir(name.to_string()))
+        } else {
+            let size = size_or_dir.parse().map_err(|_| invalid())?;
+            Ok(LsEntry::File { name: name.to_string(), size })
+        }
+    }
+
+    /// Parses a terminal transcript line by line. Every line starting with
+    /// `$ ` is a command; every other non-empty line must be output of the
+    /// most recent `ls`.
+    pub fn parse_transcript(contents: &str) -> Result<Vec<Self>, ParseError> {
+        let mut commands: Vec<Self> = vec![];
+
+        for (i, text) in contents.lines().enumerate() {
+            let line = i + 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(input) = text.strip_prefix("$ ") {
+                let input = Self::parse_command(line, input)?;
+                commands.push(Self { line, input, output: vec![] });
+            } else {
+                match commands.last_mut() {
+                    Some(cmd) if cmd.input == Command::Ls => cmd.output.push(Self::parse_entry(line, text)?),
+                    _ => return Err(ParseError { line, kind: ParseErrorKind::UnexpectedOutput }),
+                }
+            }
+        }
+
+        Ok(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_output() {
+        let commands = ExecutedCommand::parse_transcript("$ cd /\n$ ls\ndir a\n12 $b.txt\n$ cd a").unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                ExecutedCommand { line: 1, input: Command::Cd("/".to_string()), output: vec![] },
+                ExecutedCommand {
+                    line: 2,
+                    input: Command::Ls,
+                    output: vec![
+                        LsEntry::Dir("a".to_string()),
+                        LsEntry::File { name: "$b.txt".to_string(), size: 12 },
+                    ]
+                },
+                ExecutedCommand { line: 5, input: Command::Cd("a".to_string()), output: vec![] },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let err = |input| ExecutedCommand::parse_transcript(input).unwrap_err();
+
+        assert_eq!(err("$ cd /\n$ rm -rf a"), ParseError { line: 2, kind: ParseErrorKind::UnknownCommand("rm".to_string()) });
+        assert_eq!(err("$ cd"), ParseError { line: 1, kind: ParseErrorKind::WrongArguments("cd".to_string()) });
+        assert_eq!(err("$ ls -la"), ParseError { line: 1, kind: ParseErrorKind::WrongArguments("ls".to_string()) });
+        assert_eq!(err("$ cd /\ndir a"), ParseError { line: 2, kind: ParseErrorKind::UnexpectedOutput });
+        assert_eq!(err("$ ls\n12x a"), ParseError { line: 2, kind: ParseErrorKind::InvalidEntry("12x a".to_string()) });
+        assert_eq!(err("$ ls\n12x a").to_string(), "line 2: invalid ls entry `12x a`");
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{FsNode, FsTree};
+use serde_json::{json, Value};
+
+/// Renders the tree in the same indented format as the puzzle description:
+///
+/// ```text
+/// - / (dir)
+///   - a (dir)
+///     - i (file, size=584)
+/// ```
+pub fn render_tree(tree: &FsTree) -> String {
+    let mut lines: Vec<String> = vec![];
+    render_node(tree, tree.root(), 0, &mut lines);
+    lines.join("\n")
+}
+
+fn render_node(tree: &FsTree, idx: usize, depth: usize, lines: &mut Vec<String>) {
+    let node = tree.get(idx);
+    let description = if node.is_dir {
+        "dir".to_string()
+    } else {
+        format!("file, size={}", node.size)
+    };
+    lines.push(format!("{}- {} ({})", "  ".repeat(depth), node.name, description));
+
+    for &child in &node.children {
+        render_node(tree, child, depth + 1, lines);
+    }
+}
+
+/// Formats a size the way `du -h` does: powers of 1024, rounded up, with one
+/// decimal place below 10.
+pub fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = "";
+    for u in UNITS {
+        value /= 1024.0;
+        unit = u;
+        if value < 1024.0 {
+            break;
+        }
+    }
+
+    if value < 10.0 {
+        format!("{:.1}{}", (value * 10.0).ceil() / 10.0, unit)
+    } else {
+        format!("{:.0}{}", value.ceil(), unit)
+    }
+}
+
+/// One line per directory with its recursive size, largest first, like
+/// `du -h | sort -rh`.
+pub fn render_du(tree: &FsTree) -> String {
+    render_du_from(tree, tree.root())
+}
+
+/// Like `render_du`, but only for `from` and the directories beneath it.
+pub fn render_du_from(tree: &FsTree, from: usize) -> String {
+    let mut dirs: Vec<(usize, String)> = tree
+        .walk_dfs(from)
+        .filter(|(_, node)| node.is_dir)
+        .map(|(path, node)| (tree.total_size(node.idx), path))
+        .collect();
+
+    dirs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    dirs.iter()
+        .map(|(size, path)| format!("{}\t{}", human_size(*size), path))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// The whole tree as nested JSON objects. Directories include their
+/// recursive size and children; files just their size.
+pub fn to_json(tree: &FsTree) -> Value {
+    node_to_json(tree, tree.get(tree.root()))
+}
+
+fn node_to_json(tree: &FsTree, node: &FsNode) -> Value {
+    if node.is_dir {
+        let children: Vec<Value> = tree.list(node.idx).map(|child| node_to_json(tree, child)).collect();
+        json!({
+            "name": node.name,
+            "type": "dir",
+            "size": tree.total_size(node.idx),
+            "children": children,
+        })
+    } else {
+        json!({
+            "name": node.name,
+            "type": "file",
+            "size": node.size,
+        })
+    }
+}
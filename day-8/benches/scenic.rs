@@ -0,0 +1,76 @@
+use day_8::{calculate_scenic_score, scenic_scores, Trees};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Heights increase towards the bottom right, so looking up or left every
+/// tree can see all the way to the edge. This is the worst case for walking
+/// rays from each tree.
+fn generate_slope(size: usize) -> Trees {
+    (0..size)
+        .map(|r| (0..size).map(|c| (r + c) as u32).collect())
+        .collect()
+}
+
+/// A pseudo-random forest of single digit heights. With only ten heights
+/// rays stay short, so walking them is close to linear anyway.
+///
+/// Generated with xorshift so the bench needs no extra dependencies and is
+/// the same on every run.
+fn generate_forest(size: usize, mut seed: u64) -> Trees {
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    (seed % 10) as u32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// The original approach, walking four rays from every tree
+fn naive_max(trees: &Trees) -> usize {
+    let mut max = 0;
+    for r in 0..trees.len() {
+        for c in 0..trees[0].len() {
+            max = max.max(calculate_scenic_score(trees, (r, c)));
+        }
+    }
+    max
+}
+
+fn stack_max(trees: &Trees) -> usize {
+    scenic_scores(trees).into_iter().flatten().max().unwrap_or(0)
+}
+
+fn time<F: FnMut() -> usize>(iterations: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    for size in [100, 500, 1000] {
+        for (name, forest) in [("random", generate_forest(size, 0x2022_1208)), ("slope", generate_slope(size))] {
+            assert_eq!(naive_max(&forest), stack_max(&forest));
+
+            let naive_time = time(1, || naive_max(black_box(&forest)));
+            let stack_time = time(3, || stack_max(black_box(&forest)));
+
+            println!(
+                "{:>6} {:>4}x{:<4}: naive {:>10.2?}, monotonic stack {:>10.2?} ({:.1}x)",
+                name,
+                size,
+                size,
+                naive_time,
+                stack_time,
+                naive_time.as_secs_f64() / stack_time.as_secs_f64()
+            );
+        }
+    }
+}
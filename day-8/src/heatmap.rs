@@ -0,0 +1,171 @@
+use crate::{ScenicScores, Trees, VisibilityMap};
+use std::{fs::File, io, io::Write, path::Path};
+
+pub type Rgb = [u8; 3];
+
+pub const HIGHLIGHT: Rgb = [255, 0, 0];
+
+/// A simple RGB raster, one pixel per tree until scaled up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    fn from_fn<F: Fn(usize, usize) -> Rgb>(width: usize, height: usize, f: F) -> Self {
+        let pixels = (0..height)
+            .flat_map(|r| (0..width).map(move |c| (r, c)))
+            .map(|(r, c)| f(r, c))
+            .collect();
+
+        Self { width, height, pixels }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Rgb {
+        self.pixels[row * self.width + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, rgb: Rgb) {
+        self.pixels[row * self.width + col] = rgb;
+    }
+
+    /// Blows each pixel up into a `factor` x `factor` square, since a
+    /// 99x99 forest is tiny on screen.
+    pub fn scaled(&self, factor: usize) -> Self {
+        Self::from_fn(self.width * factor, self.height * factor, |r, c| self.get(r / factor, c / factor))
+    }
+
+    /// Binary PPM (P6), which almost every image viewer can open.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend(self.pixels.iter().flatten());
+        out
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()
+    }
+
+    /// Writes a PNG if the path ends in `.png`, otherwise a PPM.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut out = io::BufWriter::new(File::create(path)?);
+
+        if path.extension().is_some_and(|ext| ext == "png") {
+            self.write_png(&mut out).map_err(io::Error::other)?;
+        } else {
+            out.write_all(&self.to_ppm())?;
+        }
+        // Flush explicitly, since dropping the writer would swallow errors
+        out.flush()
+    }
+}
+
+/// Maps 0.0..=1.0 from dark blue, through green, to yellow.
+pub fn color_scale(t: f64) -> Rgb {
+    const STOPS: [Rgb; 3] = [[16, 16, 96], [32, 160, 64], [255, 230, 32]];
+
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (t.floor() as usize).min(STOPS.len() - 2);
+    let frac = t - i as f64;
+
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * frac).round() as u8;
+    [
+        lerp(STOPS[i][0], STOPS[i + 1][0]),
+        lerp(STOPS[i][1], STOPS[i + 1][1]),
+        lerp(STOPS[i][2], STOPS[i + 1][2]),
+    ]
+}
+
+/// Position of the tree with the highest scenic score, if any.
+pub fn best_tree(scores: &ScenicScores) -> Option<(usize, usize)> {
+    scores
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row)| row.iter().enumerate().map(move |(c, &score)| ((r, c), score)))
+        .max_by_key(|&(_, score)| score)
+        .map(|(point, _)| point)
+}
+
+fn highlight(mut image: Image, best: Option<(usize, usize)>) -> Image {
+    if let Some((r, c)) = best {
+        image.set(r, c, HIGHLIGHT);
+    }
+    image
+}
+
+pub fn heights_image(trees: &Trees, best: Option<(usize, usize)>) -> Image {
+    let max = trees.iter().flatten().copied().max().unwrap_or(0).max(1) as f64;
+    let width = trees.first().map_or(0, Vec::len);
+
+    highlight(Image::from_fn(width, trees.len(), |r, c| color_scale(trees[r][c] as f64 / max)), best)
+}
+
+pub fn visibility_image(map: &VisibilityMap, best: Option<(usize, usize)>) -> Image {
+    let width = map.first().map_or(0, Vec::len);
+
+    highlight(
+        Image::from_fn(width, map.len(), |r, c| color_scale(if map[r][c] { 1.0 } else { 0.0 })),
+        best,
+    )
+}
+
+/// Scenic scores are heavily skewed towards a few trees, so they're shown
+/// on a log scale.
+pub fn scenic_image(scores: &ScenicScores, best: Option<(usize, usize)>) -> Image {
+    let max = (scores.iter().flatten().copied().max().unwrap_or(0) as f64).ln_1p().max(1.0);
+    let width = scores.first().map_or(0, Vec::len);
+
+    highlight(
+        Image::from_fn(width, scores.len(), |r, c| color_scale((scores[r][c] as f64).ln_1p() / max)),
+        best,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_trees, scenic_scores};
+
+    #[test]
+    fn highlights_best_tree() {
+        let trees = parse_trees("30373\n\
+                                 25512\n\
+                                 65332\n\
+                                 33549\n\
+                                 35390");
+        let scores = scenic_scores(&trees);
+        let best = best_tree(&scores);
+        assert_eq!(best, Some((3, 2)));
+
+        let image = scenic_image(&scores, best);
+        assert_eq!(image.get(3, 2), HIGHLIGHT);
+        assert_eq!(image.get(0, 0), color_scale(0.0));
+
+        let heights = heights_image(&trees, best);
+        assert_eq!(heights.get(3, 4), color_scale(1.0));
+    }
+
+    #[test]
+    fn encodes_ppm_and_png() {
+        let image = heights_image(&parse_trees("09\n90"), None).scaled(2);
+        assert_eq!((image.width, image.height), (4, 4));
+
+        let ppm = image.to_ppm();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+
+        let mut png_bytes: Vec<u8> = vec![];
+        image.write_png(&mut png_bytes).unwrap();
+        assert!(png_bytes.starts_with(&[0x89, b'P', b'N', b'G']));
+    }
+}
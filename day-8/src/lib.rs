@@ -1,59 +1,103 @@
-use itertools::Itertools;
-use std::{collections::HashSet, fmt::Debug};
-
-type Trees = Vec<Vec<u32>>;
-
-fn parse_trees(input: &str) -> Trees {
-    input
-        .split("\n")
-        .map(str::trim)
-        .map(str::chars)
-        .map(|l| l.map(|c| c.to_digit(10).unwrap_or(0)).collect())
-        .collect()
+mod heatmap;
+mod parse;
+
+pub use heatmap::{best_tree, color_scale, heights_image, scenic_image, visibility_image, Image};
+pub use parse::{parse_trees, try_parse_trees, ParseError, ParseErrorKind, TreeFormat};
+
+use std::fmt::Debug;
+
+pub type Trees = Vec<Vec<u32>>;
+pub type VisibilityMap = Vec<Vec<bool>>;
+
+/// The direction a tree is viewed *from*, e.g. a tree visible from the
+/// `North` has only shorter trees between it and the top edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
 }
 
-pub fn calculate_visible_trees(input: &str) -> usize {
-    let data = parse_trees(input);
-    let num_rows = data.len();
-    let num_cols = data[0].len();
-
-    let mut visible: HashSet<(usize, usize)> = HashSet::new();
-
-    for i in 1..(num_rows - 1) {
-        let mut max_left = data[i][0];
-        let mut max_right = data[i][num_cols - 1];
-        let mut max_top = data[0][i];
-        let mut max_bottom = data[num_rows - 1][i];
-
-        for j in 1..(num_cols - 1) {
-            let from_left = data[i][j];
-            let from_right = data[i][num_cols - j - 1];
-            let from_top = data[j][i];
-            let from_bottom = data[num_rows - j - 1][i];
-
-            if from_left > max_left {
-                visible.insert((i, j));
-                max_left = from_left;
-            }
+impl Direction {
+    pub const CARDINAL: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
 
-            if from_right > max_right {
-                visible.insert((i, num_cols - j - 1));
-                max_right = from_right;
-            }
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+        Direction::NorthEast,
+        Direction::NorthWest,
+        Direction::SouthEast,
+        Direction::SouthWest,
+    ];
 
-            if from_top > max_top {
-                visible.insert((j, i));
-                max_top = from_top;
-            }
+    /// (row, col) step from a tree towards the edge it's viewed from.
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::South => (1, 0),
+            Direction::East => (0, 1),
+            Direction::West => (0, -1),
+            Direction::NorthEast => (-1, 1),
+            Direction::NorthWest => (-1, -1),
+            Direction::SouthEast => (1, 1),
+            Direction::SouthWest => (1, -1),
+        }
+    }
+}
+
+/// Marks every tree that can be seen from outside the grid along at least one
+/// of `directions`. Works on any rectangular grid, including empty ones.
+pub fn visibility_map(trees: &Trees, directions: &[Direction]) -> VisibilityMap {
+    let num_rows = trees.len();
+    let num_cols = trees.first().map_or(0, Vec::len);
+    let mut visible = vec![vec![false; num_cols]; num_rows];
+
+    for dir in directions {
+        let (dr, dc) = dir.delta();
 
-            if from_bottom > max_bottom {
-                visible.insert((num_rows - j - 1, i));
-                max_bottom = from_bottom;
+        // Tallest tree strictly between each tree and the edge, or None at
+        // the edge itself. Cells are visited so that the neighbour towards
+        // the edge has always been filled in first.
+        let mut tallest: Vec<Vec<Option<u32>>> = vec![vec![None; num_cols]; num_rows];
+        let rows: Vec<usize> = if dr > 0 { (0..num_rows).rev().collect() } else { (0..num_rows).collect() };
+        let cols: Vec<usize> = if dc > 0 { (0..num_cols).rev().collect() } else { (0..num_cols).collect() };
+
+        for &r in &rows {
+            for &c in &cols {
+                let neighbour = r
+                    .checked_add_signed(dr)
+                    .zip(c.checked_add_signed(dc))
+                    .filter(|&(nr, nc)| nr < num_rows && nc < num_cols);
+
+                tallest[r][c] = neighbour.map(|(nr, nc)| match tallest[nr][nc] {
+                    Some(h) => h.max(trees[nr][nc]),
+                    None => trees[nr][nc],
+                });
+
+                if tallest[r][c].is_none_or(|h| trees[r][c] > h) {
+                    visible[r][c] = true;
+                }
             }
         }
     }
 
-    visible.len() + 2 * num_rows + 2 * num_cols - 4
+    visible
+}
+
+pub fn count_visible(map: &VisibilityMap) -> usize {
+    map.iter().flatten().filter(|&&v| v).count()
+}
+
+pub fn calculate_visible_trees(input: &str) -> usize {
+    let data = parse_trees(input);
+    count_visible(&visibility_map(&data, &Direction::CARDINAL))
 }
 
 pub fn find_dist_to_equal_or_greater_height<I>(trees: &Trees, height: u32, iter: I) -> usize
@@ -63,7 +107,7 @@ I: ExactSizeIterator<Item = (usize, usize)> + Debug
     let total = iter.len();
     match iter
         .enumerate()
-        .find(|(n, pt)| {
+        .find(|(_, pt)| {
             trees[pt.0][pt.1] >= height
         })
     {
@@ -91,19 +135,66 @@ pub fn calculate_scenic_score(trees: &Trees, point: (usize, usize)) -> usize {
     score_left * score_right * score_top * score_bottom
 }
 
+pub type ScenicScores = Vec<Vec<usize>>;
+
+/// Walks `len` trees, reporting for each one how many trees it can see
+/// looking back towards the first, stopping at the first tree at least as
+/// tall.
+///
+/// Keeps a stack of indices whose heights never increase from bottom to top,
+/// so each tree is pushed and popped at most once.
+fn viewing_distances<H, R>(len: usize, height: H, stack: &mut Vec<usize>, mut record: R)
+where
+    H: Fn(usize) -> u32,
+    R: FnMut(usize, usize),
+{
+    stack.clear();
+
+    for i in 0..len {
+        let h = height(i);
+        while stack.last().is_some_and(|&j| height(j) < h) {
+            stack.pop();
+        }
+        let dist = match stack.last() {
+            Some(&j) => i - j,
+            None => i,
+        };
+        stack.push(i);
+        record(i, dist);
+    }
+}
+
+/// Scenic score of every tree in O(rows * cols), using a monotonic stack
+/// per row and column in each direction.
+pub fn scenic_scores(trees: &Trees) -> ScenicScores {
+    let num_rows = trees.len();
+    let num_cols = trees.first().map_or(0, Vec::len);
+    let mut scores = vec![vec![1; num_cols]; num_rows];
+    let mut stack: Vec<usize> = Vec::with_capacity(num_rows.max(num_cols));
+
+    for r in 0..num_rows {
+        let last = num_cols.saturating_sub(1);
+        viewing_distances(num_cols, |c| trees[r][c], &mut stack, |c, d| scores[r][c] *= d);
+        viewing_distances(num_cols, |c| trees[r][last - c], &mut stack, |c, d| scores[r][last - c] *= d);
+    }
+
+    for c in 0..num_cols {
+        let last = num_rows.saturating_sub(1);
+        viewing_distances(num_rows, |r| trees[r][c], &mut stack, |r, d| scores[r][c] *= d);
+        viewing_distances(num_rows, |r| trees[last - r][c], &mut stack, |r, d| scores[last - r][c] *= d);
+    }
+
+    scores
+}
+
 pub fn calculate_max_scenic_score(input: &str) -> usize {
     let data = parse_trees(input);
 
-    let num_rows = data.len();
-    let num_cols = data[0].len();
-
-    let max_scenic_score: usize = (0..num_cols)
-        .cartesian_product(0..num_rows)
-        .map(|point| calculate_scenic_score(&data, point))
+    scenic_scores(&data)
+        .into_iter()
+        .flatten()
         .max()
-        .unwrap();
-
-    max_scenic_score
+        .unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -132,4 +223,73 @@ mod tests {
         assert_eq!(calculate_scenic_score(&parse_trees(example), (3, 2)), 8);
         assert_eq!(calculate_max_scenic_score(example), 8);
     }
+
+    #[test]
+    fn visibility_map_works() {
+        let example = "30373\n\
+                       25512\n\
+                       65332\n\
+                       33549\n\
+                       35390";
+
+        let map = visibility_map(&parse_trees(example), &Direction::CARDINAL);
+        assert_eq!(map[1], vec![true, true, true, false, true]);
+        assert_eq!(map[2], vec![true, true, false, true, true]);
+        assert_eq!(map[3], vec![true, false, true, false, true]);
+    }
+
+    #[test]
+    fn visible_trees_non_square() {
+        let example = "3037\n\
+                       2551\n\
+                       6532\n\
+                       3354\n\
+                       3539\n\
+                       1111";
+
+        // Edges, plus every interior tree except the 3 at (3, 1)
+        assert_eq!(calculate_visible_trees(example), 16 + 7);
+    }
+
+    #[test]
+    fn visible_trees_small_grids() {
+        assert_eq!(calculate_visible_trees("5"), 1);
+        assert_eq!(calculate_visible_trees("12"), 2);
+        assert_eq!(calculate_visible_trees("1\n2\n3"), 3);
+        assert_eq!(count_visible(&visibility_map(&vec![], &Direction::ALL)), 0);
+    }
+
+    #[test]
+    fn diagonal_visibility() {
+        let trees = parse_trees("999\n\
+                                 959\n\
+                                 199");
+
+        let cardinal = visibility_map(&trees, &Direction::CARDINAL);
+        assert!(!cardinal[1][1]);
+
+        let all = visibility_map(&trees, &Direction::ALL);
+        assert!(all[1][1]);
+
+        let diagonal = visibility_map(&trees, &[Direction::SouthWest]);
+        assert_eq!(diagonal, vec![vec![true, false, true], vec![true, true, false], vec![true, true, true]]);
+    }
+
+    #[test]
+    fn scenic_scores_match_per_tree_scores() {
+        let example = "30373\n\
+                       25512\n\
+                       65332\n\
+                       33549\n\
+                       35390\n\
+                       12345";
+        let trees = parse_trees(example);
+        let scores = scenic_scores(&trees);
+
+        for (r, row) in scores.iter().enumerate() {
+            for (c, &score) in row.iter().enumerate() {
+                assert_eq!(score, calculate_scenic_score(&trees, (r, c)), "({}, {})", r, c);
+            }
+        }
+    }
 }
@@ -1,8 +1,42 @@
-use day_8::{calculate_visible_trees, calculate_max_scenic_score};
+use day_8::{
+    best_tree, calculate_max_scenic_score, calculate_visible_trees, heights_image, parse_trees, scenic_image,
+    scenic_scores, visibility_image, visibility_map, Direction,
+};
+use std::{env, fs, path::PathBuf};
 
 fn main() {
     let contents = include_str!("input.txt").trim();
 
     println!("{}", calculate_visible_trees(contents));
     println!("{}", calculate_max_scenic_score(contents));
+
+    // Usage: day-8 [--export <dir>] [--ppm] [--scale <n>]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arg_value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("expected a value after {}", flag)))
+    };
+
+    if let Some(dir) = arg_value("--export") {
+        let dir = PathBuf::from(dir);
+        let ext = if args.iter().any(|a| a == "--ppm") { "ppm" } else { "png" };
+        let scale: usize = arg_value("--scale").map_or(8, |n| n.parse().expect("expected --scale to be numeric"));
+
+        let trees = parse_trees(contents);
+        let scores = scenic_scores(&trees);
+        let best = best_tree(&scores);
+
+        fs::create_dir_all(&dir).expect("could not create export dir");
+        let images = [
+            ("heights", heights_image(&trees, best)),
+            ("visibility", visibility_image(&visibility_map(&trees, &Direction::CARDINAL), best)),
+            ("scenic", scenic_image(&scores, best)),
+        ];
+        for (name, image) in images {
+            let path = dir.join(format!("{}.{}", name, ext));
+            image.scaled(scale).save(&path).expect("could not write image");
+            println!("wrote {}", path.display());
+        }
+    }
 }
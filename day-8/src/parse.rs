@@ -0,0 +1,142 @@
+use crate::Trees;
+use std::fmt;
+
+/// How heights are written in a grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeFormat {
+    /// One digit per tree with no separators, as in the puzzle input
+    Digits,
+    /// Heights of any size separated by whitespace, e.g. `12 7 130`
+    WhitespaceSeparated,
+}
+
+impl TreeFormat {
+    /// Grids with whitespace inside any row are whitespace separated.
+    pub fn detect(input: &str) -> Self {
+        if input.lines().any(|line| line.trim().contains(char::is_whitespace)) {
+            TreeFormat::WhitespaceSeparated
+        } else {
+            TreeFormat::Digits
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    InvalidHeight(String),
+    RaggedRow { expected: usize, found: usize },
+}
+
+/// Rows and columns are 1-based. Columns count trees, not characters, so
+/// for whitespace separated grids the column is the index of the height.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub row: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::InvalidHeight(height) => {
+                write!(f, "row {}, column {}: invalid height `{}`", self.row, self.column, height)
+            }
+            ParseErrorKind::RaggedRow { expected, found } => {
+                write!(f, "row {}: expected {} trees but found {}", self.row, expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn parse_row(row: usize, line: &str, format: TreeFormat) -> Result<Vec<u32>, ParseError> {
+    let invalid = |column: usize, height: &str| ParseError {
+        row,
+        column,
+        kind: ParseErrorKind::InvalidHeight(height.to_string()),
+    };
+
+    match format {
+        TreeFormat::Digits => line
+            .chars()
+            .enumerate()
+            .map(|(i, c)| c.to_digit(10).ok_or_else(|| invalid(i + 1, &c.to_string())))
+            .collect(),
+        TreeFormat::WhitespaceSeparated => line
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, height)| height.parse().map_err(|_| invalid(i + 1, height)))
+            .collect(),
+    }
+}
+
+/// Parses a grid, rejecting anything that isn't a height and any row whose
+/// length differs from the first.
+pub fn try_parse_trees(input: &str, format: TreeFormat) -> Result<Trees, ParseError> {
+    let trees: Trees = input
+        .trim()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_row(i + 1, line.trim(), format))
+        .collect::<Result<_, _>>()?;
+
+    if let Some(first) = trees.first() {
+        let expected = first.len();
+        if let Some((i, row)) = trees.iter().enumerate().find(|(_, row)| row.len() != expected) {
+            return Err(ParseError {
+                row: i + 1,
+                column: expected.min(row.len()) + 1,
+                kind: ParseErrorKind::RaggedRow { expected, found: row.len() },
+            });
+        }
+    }
+
+    Ok(trees)
+}
+
+/// Parses a grid in whichever format it appears to be in, panicking with the
+/// position of the problem if it's malformed.
+pub fn parse_trees(input: &str) -> Trees {
+    try_parse_trees(input, TreeFormat::detect(input)).unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_digits() {
+        assert_eq!(try_parse_trees("303\n255\n", TreeFormat::Digits), Ok(vec![vec![3, 0, 3], vec![2, 5, 5]]));
+        assert_eq!(try_parse_trees("", TreeFormat::Digits), Ok(vec![]));
+    }
+
+    #[test]
+    fn parses_whitespace_separated() {
+        let input = "10 200  3\n\
+                     7    0 42";
+
+        assert_eq!(TreeFormat::detect(input), TreeFormat::WhitespaceSeparated);
+        assert_eq!(parse_trees(input), vec![vec![10, 200, 3], vec![7, 0, 42]]);
+    }
+
+    #[test]
+    fn rejects_non_digits() {
+        let err = try_parse_trees("303\n2x5", TreeFormat::Digits).unwrap_err();
+
+        assert_eq!(err, ParseError { row: 2, column: 2, kind: ParseErrorKind::InvalidHeight("x".to_string()) });
+        assert_eq!(err.to_string(), "row 2, column 2: invalid height `x`");
+
+        let err = try_parse_trees("1 2\n3 -4", TreeFormat::WhitespaceSeparated).unwrap_err();
+        assert_eq!(err, ParseError { row: 2, column: 2, kind: ParseErrorKind::InvalidHeight("-4".to_string()) });
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let err = try_parse_trees("303\n25\n653", TreeFormat::Digits).unwrap_err();
+
+        assert_eq!(err, ParseError { row: 2, column: 3, kind: ParseErrorKind::RaggedRow { expected: 3, found: 2 } });
+        assert_eq!(err.to_string(), "row 2: expected 3 trees but found 2");
+    }
+}
@@ -1,10 +1,14 @@
+mod trajectory;
+
+pub use trajectory::Trajectory;
+
 use std::collections::HashSet;
 
-enum Direction { Up, Down, Left, Right }
+enum Direction { Up, Down, Left, Right, UpLeft, UpRight, DownLeft, DownRight }
 
 struct MoveInstruction {
     dir: Direction,
-    times: u8
+    times: usize
 }
 
 impl MoveInstruction {
@@ -15,18 +19,22 @@ impl MoveInstruction {
             "R" => Direction::Right,
             "U" => Direction::Up,
             "D" => Direction::Down,
+            "UL" => Direction::UpLeft,
+            "UR" => Direction::UpRight,
+            "DL" => Direction::DownLeft,
+            "DR" => Direction::DownRight,
             _ => panic!("Invalid direction")
         };
 
-        let times: u8 = parts.next().expect("expect times").parse().expect("expect times to be numeric");
+        let times: usize = parts.next().expect("expect times").parse().expect("expect times to be numeric");
 
         Self { dir, times }
     }
 }
 
-type Point = (i32, i32);
+pub type Point = (i32, i32);
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 struct Knot(i32, i32);
 
 impl Knot {
@@ -34,84 +42,151 @@ impl Knot {
         Knot(0, 0)
     }
 
-    fn adjust_to(&mut self, knot: &Knot) {
-        // If the knot is two away in any diagonal direction,
-        // split the difference
-        if (knot.0 - self.0).abs() == 2 && (knot.1 - self.1).abs() == 2 {
-            self.0 = (knot.0 + self.0) / 2;
-            self.1 = (knot.1 + self.1) / 2;
-        } else if knot.1 > self.1 + 1 {
-            self.0 = knot.0;
-            self.1 = knot.1 - 1;
-        } else if knot.1 < self.1 - 1 {
-            self.0 = knot.0;
-            self.1 = knot.1 + 1;
-        } else if knot.0 < self.0 - 1 {
-            self.0 = knot.0 + 1;
-            self.1 = knot.1;
-        } else if knot.0 > self.0 + 1 {
-            self.0 = knot.0 - 1;
-            self.1 = knot.1;
+    /// If further than `slack` from the leading knot on either axis, moves
+    /// one step towards it on each axis, which covers straight and diagonal
+    /// moves alike.
+    fn follow(&mut self, knot: &Knot, slack: i32) {
+        if (knot.0 - self.0).abs() > slack || (knot.1 - self.1).abs() > slack {
+            self.0 += (knot.0 - self.0).signum();
+            self.1 += (knot.1 - self.1).signum();
         }
     }
 
     fn step(&mut self, dir: &Direction) {
-        match dir {
-            Direction::Up => {
-                self.1 = self.1 + 1;
-            },
-            Direction::Down => {
-                self.1 = self.1 - 1;
-            },
-            Direction::Left => {
-                self.0 = self.0 - 1;
-            },
-            Direction::Right => {
-                self.0 = self.0 + 1;
-            }
+        let (dx, dy) = match dir {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        };
+
+        self.0 += dx;
+        self.1 += dy;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Link {
+    leader: usize,
+    slack: i32,
+}
+
+/// Describes how knots are tied together: knot 0 is the head, and every
+/// other knot follows a single leader, so knots form a tree rooted at the
+/// head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RopeTopology {
+    links: Vec<Option<Link>>,
+}
+
+impl Default for RopeTopology {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RopeTopology {
+    /// Just a head, ready for knots to be added.
+    pub fn new() -> Self {
+        Self { links: vec![None] }
+    }
+
+    /// The puzzle's rope: `size` knots in a line, each following the one
+    /// before it with a slack of one. Panics if `size` is zero, since every
+    /// rope has a head.
+    pub fn chain(size: u8) -> Self {
+        assert!(size > 0, "a rope needs at least one knot");
+
+        let mut topology = Self::new();
+        for i in 1..size as usize {
+            topology.add_knot(i - 1, 1);
         }
+        topology
+    }
+
+    /// Adds a knot following `leader`, which moves whenever it's more than
+    /// `slack` steps away on either axis. Returns the new knot's index.
+    pub fn add_knot(&mut self, leader: usize, slack: u16) -> usize {
+        assert!(leader < self.links.len(), "knot {} does not exist", leader);
+
+        self.links.push(Some(Link { leader, slack: i32::from(slack) }));
+        self.links.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Always false, since every rope has a head.
+    pub fn is_empty(&self) -> bool {
+        false
     }
 }
 
 struct Rope {
-    knots: Vec<Knot>
+    knots: Vec<Knot>,
+    topology: RopeTopology,
 }
 
 impl Rope {
-    fn new(size: u8) -> Self {
+    fn new(topology: &RopeTopology) -> Self {
         Self {
-            knots: (0..size).map(|_| Knot::new()).collect()
+            knots: (0..topology.len()).map(|_| Knot::new()).collect(),
+            topology: topology.clone(),
         }
     }
 
     fn step(&mut self, dir: &Direction) {
         self.knots[0].step(dir);
 
+        // Leaders always come before their followers, so one pass in order
+        // settles every knot
         for i in 1..self.knots.len() {
+            let link = self.topology.links[i].expect("expected every knot but the head to have a leader");
             // Needed to get mutable refs to multiple vector elements at once
             let (left, right) = self.knots.split_at_mut(i);
-            // dbg!(i, left.len(), right.len());
-            right[0].adjust_to(&left[i-1]);
+            right[0].follow(&left[link.leader], link.slack);
         }
     }
-}
 
-pub fn compute_tail_locations(contents: &str, rope_size: u8) -> HashSet<Point> {
-    let mut rope = Rope::new(rope_size);
-    let mut visited: HashSet<Point> = HashSet::new();
+    fn positions(&self) -> Vec<Point> {
+        self.knots.iter().map(|knot| (knot.0, knot.1)).collect()
+    }
+}
 
-    visited.insert((0,0));
+/// Runs a motion script, calling `on_step` with the rope after every step.
+fn simulate<F: FnMut(&Rope)>(contents: &str, topology: &RopeTopology, mut on_step: F) {
+    let mut rope = Rope::new(topology);
+    on_step(&rope);
 
-    for instr in contents.split("\n").map(MoveInstruction::parse) {
+    for instr in contents.split('\n').map(MoveInstruction::parse) {
         for _ in 0..instr.times {
             rope.step(&instr.dir);
-            let tail = rope.knots.last().expect("expected last knot");
-            visited.insert((tail.0, tail.1));
+            on_step(&rope);
         }
     }
+}
+
+/// Every position `knot` occupies while running the motion script.
+pub fn compute_visited(contents: &str, topology: &RopeTopology, knot: usize) -> HashSet<Point> {
+    assert!(knot < topology.len(), "knot {} does not exist", knot);
+
+    let mut visited: HashSet<Point> = HashSet::new();
+    simulate(contents, topology, |rope| {
+        let k = &rope.knots[knot];
+        visited.insert((k.0, k.1));
+    });
     visited
 }
 
+pub fn compute_tail_locations(contents: &str, rope_size: u8) -> HashSet<Point> {
+    compute_visited(contents, &RopeTopology::chain(rope_size), rope_size as usize - 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,4 +222,161 @@ mod tests {
         // }
         assert_eq!(result.len(), 36);
     }
+
+    #[test]
+    fn example_1_short_rope() {
+        let example = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+        assert_eq!(compute_tail_locations(example, 2).len(), 13);
+    }
+
+    #[test]
+    fn follows_every_relative_position() {
+        // Leader offsets within two steps on each axis, and where a knot at
+        // the origin should end up after following
+        for dx in -2..=2 {
+            for dy in -2..=2 {
+                let leader = Knot(dx, dy);
+                let mut knot = Knot::new();
+                knot.follow(&leader, 1);
+
+                let expected = if dx.abs() <= 1 && dy.abs() <= 1 {
+                    Knot(0, 0)
+                } else {
+                    Knot(dx.signum(), dy.signum())
+                };
+
+                assert_eq!(knot, expected, "leader at ({}, {})", dx, dy);
+                assert!((leader.0 - knot.0).abs() <= 1 && (leader.1 - knot.1).abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn follows_specific_positions() {
+        let cases = [
+            ((2, 0), (1, 0)),
+            ((0, -2), (0, -1)),
+            ((2, 1), (1, 1)),
+            ((1, 2), (1, 1)),
+            ((-2, 1), (-1, 1)),
+            ((2, 2), (1, 1)),
+            ((-2, -2), (-1, -1)),
+            ((1, 1), (0, 0)),
+        ];
+
+        for ((lx, ly), (ex, ey)) in cases {
+            let mut knot = Knot::new();
+            knot.follow(&Knot(lx, ly), 1);
+            assert_eq!(knot, Knot(ex, ey), "leader at ({}, {})", lx, ly);
+        }
+    }
+
+    #[test]
+    fn diagonal_moves() {
+        let trajectory = Trajectory::record("UR 3\nDR 2\nDL 1\nUL 1", 3);
+
+        assert_eq!(trajectory.frames[3], vec![(3, 3), (2, 2), (1, 1)]);
+        // Turning down and right, the knots swing round behind the head
+        assert_eq!(trajectory.frames[5], vec![(5, 1), (4, 1), (3, 1)]);
+        assert_eq!(trajectory.path(0).last(), Some((3, 1)));
+        assert_eq!(trajectory.visited(2).len(), 4);
+    }
+
+    #[test]
+    fn large_step_counts() {
+        assert_eq!(compute_tail_locations("R 1000", 10).len(), 992);
+        assert_eq!(compute_tail_locations("UR 300", 2).len(), 300);
+    }
+
+    #[test]
+    fn branching_rope() {
+        // A chain of two knots off the head, plus a loose knot with a slack
+        // of three on its own branch
+        let mut topology = RopeTopology::new();
+        let first = topology.add_knot(0, 1);
+        let second = topology.add_knot(first, 1);
+        let loose = topology.add_knot(0, 3);
+
+        let visited = |knot| compute_visited("R 4\nU 2", &topology, knot);
+
+        assert_eq!(visited(0).len(), 7);
+        assert_eq!(visited(first).len(), 5);
+        assert_eq!(visited(second).len(), 4);
+        assert_eq!(visited(loose), HashSet::from([(0, 0), (1, 0)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "knot 2 does not exist")]
+    fn visited_by_missing_knot() {
+        compute_visited("R 1", &RopeTopology::chain(2), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one knot")]
+    fn empty_rope() {
+        compute_tail_locations("R 1", 0);
+    }
+
+    #[test]
+    fn slack_zero_tracks_leader() {
+        let mut topology = RopeTopology::new();
+        let knot = topology.add_knot(0, 0);
+
+        let trajectory = Trajectory::record_topology("R 2\nUL 1", &topology);
+        assert_eq!(trajectory.path(knot).collect::<Vec<Point>>(), trajectory.path(0).collect::<Vec<Point>>());
+    }
+
+    #[test]
+    fn chain_matches_puzzle_rope() {
+        let example = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+        assert_eq!(compute_visited(example, &RopeTopology::chain(10), 9).len(), 36);
+    }
+
+    #[test]
+    fn trajectory_matches_tail_locations() {
+        let example = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+        let trajectory = Trajectory::record(example, 10);
+
+        assert_eq!(trajectory.frames.len(), 1 + 5 + 8 + 8 + 3 + 17 + 10 + 25 + 20);
+        assert_eq!(trajectory.visited(9), compute_tail_locations(example, 10));
+    }
+
+    #[test]
+    fn renders_frames() {
+        let trajectory = Trajectory::record("R 4\nU 4", 10);
+
+        assert_eq!(
+            trajectory.render_frame(8),
+            "....H\n\
+             ....1\n\
+             ..432\n\
+             .5...\n\
+             6...."
+        );
+        assert_eq!(Trajectory::record("R 2", 2).render_frame(2), "sTH");
+    }
+
+    #[test]
+    fn renders_visited() {
+        let example = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+        let trajectory = Trajectory::record(example, 2);
+
+        assert_eq!(
+            trajectory.render_visited(1),
+            "..##..\n\
+             ...##.\n\
+             .####.\n\
+             ....#.\n\
+             s###.."
+        );
+    }
+
+    #[test]
+    fn renders_svg() {
+        let svg = Trajectory::record("R 2\nU 2", 2).to_svg(1);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("fill=\"#bbb\"").count(), 3);
+        assert_eq!(svg.matches("<polyline").count(), 2);
+    }
 }
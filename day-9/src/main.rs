@@ -1,7 +1,31 @@
-use day_9::compute_tail_locations;
+use day_9::{compute_tail_locations, Trajectory};
+use std::{env, fs};
 
 fn main() {
     let contents = include_str!("input.txt").trim();
-    println!("{}", compute_tail_locations(contents, 2).len());
-    println!("{}", compute_tail_locations(contents, 10).len()); // I get 2509, which is wrong :(
+
+    // Usage: day-9 [--frames | --visited | --svg <path>] [--knots <n>]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arg_value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("expected a value after {}", flag)))
+    };
+    let knots: u8 = arg_value("--knots").map_or(10, |n| n.parse().expect("expected --knots to be numeric"));
+    assert!(knots > 0, "expected --knots to be at least 1");
+    let tail = knots as usize - 1;
+
+    if args.iter().any(|a| a == "--frames") {
+        let trajectory = Trajectory::record(contents, knots);
+        for step in 0..trajectory.frames.len() {
+            println!("{}\n", trajectory.render_frame(step));
+        }
+    } else if args.iter().any(|a| a == "--visited") {
+        println!("{}", Trajectory::record(contents, knots).render_visited(tail));
+    } else if let Some(path) = arg_value("--svg") {
+        fs::write(path, Trajectory::record(contents, knots).to_svg(tail)).expect("could not write svg");
+    } else {
+        println!("{}", compute_tail_locations(contents, 2).len());
+        println!("{}", compute_tail_locations(contents, 10).len());
+    }
 }
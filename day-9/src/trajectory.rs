@@ -0,0 +1,143 @@
+use crate::{simulate, Point, RopeTopology};
+use std::collections::HashSet;
+
+/// Every knot's position after every step of a motion script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trajectory {
+    /// `frames[step][knot]`, where step 0 is the starting position and knot
+    /// 0 is the head
+    pub frames: Vec<Vec<Point>>,
+}
+
+/// Inclusive (min_x, min_y, max_x, max_y) of a set of points.
+type Bounds = (i32, i32, i32, i32);
+
+impl Trajectory {
+    pub fn record(contents: &str, rope_size: u8) -> Self {
+        Self::record_topology(contents, &RopeTopology::chain(rope_size))
+    }
+
+    pub fn record_topology(contents: &str, topology: &RopeTopology) -> Self {
+        let mut frames: Vec<Vec<Point>> = vec![];
+        simulate(contents, topology, |rope| frames.push(rope.positions()));
+
+        Self { frames }
+    }
+
+    pub fn num_knots(&self) -> usize {
+        self.frames.first().map_or(0, Vec::len)
+    }
+
+    pub fn path(&self, knot: usize) -> impl Iterator<Item = Point> + '_ {
+        self.frames.iter().map(move |frame| frame[knot])
+    }
+
+    pub fn visited(&self, knot: usize) -> HashSet<Point> {
+        self.path(knot).collect()
+    }
+
+    /// Covers every knot in every frame, plus the origin, so all frames
+    /// render at the same size.
+    fn bounds(&self) -> Bounds {
+        self.frames
+            .iter()
+            .flatten()
+            .fold((0, 0, 0, 0), |(min_x, min_y, max_x, max_y), &(x, y)| {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            })
+    }
+
+    fn render_grid<F: Fn(Point) -> char>(&self, cell: F) -> String {
+        let (min_x, min_y, max_x, max_y) = self.bounds();
+
+        // Up is positive y, so render the highest row first
+        (min_y..=max_y)
+            .rev()
+            .map(|y| (min_x..=max_x).map(|x| cell((x, y))).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn knot_label(&self, knot: usize) -> char {
+        match knot {
+            0 => 'H',
+            1 if self.num_knots() == 2 => 'T',
+            n => char::from_digit(n as u32, 36).unwrap_or('#'),
+        }
+    }
+
+    /// Renders the rope after `step` like the puzzle's diagrams: `H` for the
+    /// head, `T` or the knot number for the rest, and `s` for the start.
+    /// Where knots overlap, the one nearest the head is shown.
+    pub fn render_frame(&self, step: usize) -> String {
+        let frame = &self.frames[step];
+
+        self.render_grid(|point| match frame.iter().position(|&p| p == point) {
+            Some(knot) => self.knot_label(knot),
+            None if point == (0, 0) => 's',
+            None => '.',
+        })
+    }
+
+    /// Renders every cell `knot` has visited as `#`, with the start as `s`.
+    pub fn render_visited(&self, knot: usize) -> String {
+        let visited = self.visited(knot);
+
+        self.render_grid(|point| {
+            if point == (0, 0) {
+                's'
+            } else if visited.contains(&point) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+    }
+
+    /// An SVG of the cells visited by `knot`, with the paths of the head and
+    /// of `knot` drawn over them.
+    pub fn to_svg(&self, knot: usize) -> String {
+        const CELL: i32 = 10;
+        let (min_x, min_y, max_x, max_y) = self.bounds();
+        let width = (max_x - min_x + 1) * CELL;
+        let height = (max_y - min_y + 1) * CELL;
+
+        // Flip y, since SVG coordinates grow downwards
+        let to_svg = |(x, y): Point| ((x - min_x) * CELL, (max_y - y) * CELL);
+        let polyline = |path: Vec<Point>, color: &str| {
+            let points: Vec<String> = path
+                .into_iter()
+                .map(to_svg)
+                .map(|(x, y)| format!("{},{}", x + CELL / 2, y + CELL / 2))
+                .collect();
+            format!(
+                "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+                points.join(" "),
+                color
+            )
+        };
+
+        let mut visited: Vec<Point> = self.visited(knot).into_iter().collect();
+        visited.sort();
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        );
+        svg.push_str(&format!("  <rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height));
+        for point in visited {
+            let (x, y) = to_svg(point);
+            svg.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#bbb\"/>\n",
+                x, y, CELL, CELL
+            ));
+        }
+        svg.push_str(&polyline(self.path(0).collect(), "#d33"));
+        if knot != 0 {
+            svg.push_str(&polyline(self.path(knot).collect(), "#33d"));
+        }
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}